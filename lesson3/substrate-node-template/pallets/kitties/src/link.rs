@@ -1,3 +1,8 @@
+//! Linked list of the owned kitties of lesson3.
+//!
+//! Lesson5 moved this into the `linked-list` crate, whose items also keep the list length.
+//! Lesson3 is a separate workspace and keeps its own storage layout, so it keeps this copy.
+
 use frame_support::{StorageMap, Parameter};
 use sp_runtime::traits::Member;
 use codec::{Encode, Decode};
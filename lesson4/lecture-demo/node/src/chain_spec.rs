@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	SudoConfig, SystemConfig, OcwDemoConfig, WASM_BINARY, Signature
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -151,7 +151,23 @@ fn testnet_genesis(
 		}),
 		pallet_sudo: Some(SudoConfig {
 			// Assign network admin rights.
-			key: root_key,
+			key: root_key.clone(),
+		}),
+		pallet_ocw_demo: Some(OcwDemoConfig {
+			// DOT/USD price feeds, queried every 10 blocks
+			feeds: vec![
+				("https://api.coincap.io/v2/assets/polkadot", vec!["data", "priceUsd"]),
+				("https://api.coingecko.com/api/v3/simple/price?ids=polkadot&vs_currencies=usd", vec!["polkadot", "usd"]),
+				("https://min-api.cryptocompare.com/data/price?fsym=DOT&tsyms=USD", vec!["USD"]),
+			].into_iter().map(|(url, json_path)| (
+				url.as_bytes().to_vec(),
+				vec![],
+				json_path.into_iter().map(|key| key.as_bytes().to_vec()).collect(),
+				0,
+				10,
+			)).collect(),
+			// The `demo` key of the root account has to be in the keystore to submit prices
+			oracles: vec![root_key],
 		}),
 	}
 }
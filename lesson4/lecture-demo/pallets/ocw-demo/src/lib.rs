@@ -7,12 +7,13 @@ mod tests;
 
 use core::{convert::TryInto, fmt};
 use frame_support::{
-	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult,
+	debug, decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
+	traits::{Get, UnixTime}, weights::Weight, IterableStorageMap,
 };
 use parity_scale_codec::{Decode, Encode};
 
 use frame_system::{
-	self as system, ensure_none, ensure_signed,
+	self as system, ensure_none, ensure_root, ensure_signed,
 	offchain::{
 		AppCrypto, CreateSignedTransaction, SendSignedTransaction, SendUnsignedTransaction,
 		SignedPayload, SigningTypes, Signer, SubmitTransaction,
@@ -20,7 +21,8 @@ use frame_system::{
 };
 use sp_core::crypto::KeyTypeId;
 use sp_runtime::{
-	RuntimeDebug,
+	FixedPointNumber, FixedU128, Permill, RuntimeAppPublic, RuntimeDebug,
+	traits::{Hash, IdentifyAccount, Saturating},
 	offchain as rt_offchain,
 	offchain::{
 		storage::StorageValueRef,
@@ -50,6 +52,9 @@ pub const NUM_VEC_LEN: usize = 10;
 pub const PRICES_VEC_LEN: usize = 10;
 /// The type to sign and send transactions.
 pub const UNSIGNED_TXS_PRIORITY: u64 = 100;
+/// Blocks an unsigned transaction stays in the pool, a signed price is only accepted this long
+///   after the block it is signed for.
+pub const UNSIGNED_TXS_LONGEVITY: u32 = 3;
 
 // We are fetching information from the github public API about organization`substrate-developer-hub`.
 pub const HTTP_REMOTE_REQUEST: &str = "https://api.github.com/orgs/substrate-developer-hub";
@@ -59,8 +64,73 @@ pub const FETCH_TIMEOUT_PERIOD: u64 = 3000; // in milli-seconds
 pub const LOCK_TIMEOUT_EXPIRATION: u64 = FETCH_TIMEOUT_PERIOD + 1000; // in milli-seconds
 pub const LOCK_BLOCK_EXPIRATION: u32 = 3; // in block number
 
-pub const COINCAP_REMOTE_URL: &str = "https://api.coincap.io/v2/assets/polkadot";
-// pub const COINCAP_REMOTE_URL: &[u8] = b"https://api.coincap.io/v2/assets/polkadot";
+/// A price in USD.
+pub type Price = FixedU128;
+/// Decimals of `Price`, the accuracy of `FixedU128` is 10^18.
+pub const PRICE_DECIMALS: u32 = 18;
+
+pub type FeedId = u32;
+pub type RoundId = u32;
+
+/// The median of the prices submitted by the oracles in a block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RoundPrice<BlockNumber> {
+	pub round: RoundId,
+	pub price: Price,
+	/// Block the round is finalized in.
+	pub block_number: BlockNumber,
+	/// Unix time of the block in milliseconds.
+	pub timestamp: u64,
+	/// Number of oracles that submitted a price.
+	pub participants: u32,
+}
+
+pub type RoundPriceOf<T> = RoundPrice<<T as system::Trait>::BlockNumber>;
+
+/// Latest price finalized by the oracles, for other pallets.
+pub trait PriceProvider<BlockNumber> {
+	/// The latest price and the number of blocks since it was finalized.
+	fn latest_price() -> Option<(Price, BlockNumber)>;
+}
+
+impl<BlockNumber> PriceProvider<BlockNumber> for () {
+	fn latest_price() -> Option<(Price, BlockNumber)> {
+		None
+	}
+}
+
+/// A remote source of the price, queried by the offchain worker.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Feed<BlockNumber> {
+	pub url: Vec<u8>,
+	/// Headers of the HTTP request. (name, value)
+	pub headers: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Keys from the root of the JSON response to the price, given as a string or a number.
+	///   e.g. `["data", "priceUsd"]` for `{"data": {"priceUsd": "4.25"}}`
+	pub json_path: Vec<Vec<u8>>,
+	/// Decimals of the price in the response, e.g. 2 for a price given in cents.
+	///   At most `PRICE_DECIMALS`.
+	pub decimals: u8,
+	/// Blocks between two queries of the feed.
+	pub interval: BlockNumber,
+}
+
+pub type FeedOf<T> = Feed<<T as system::Trait>::BlockNumber>;
+
+/// Storage layout versions of the pallet.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+	/// Prices truncated to `u32`.
+	V1IntegerPrices,
+	/// Prices stored as `Price`.
+	V2FixedPrices,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1IntegerPrices
+	}
+}
 
 /// Based on the above `KeyTypeId` we need to generate a pallet-specific crypto type wrapper.
 /// We can utilize the supported crypto kinds (`sr25519`, `ed25519` and `ecdsa`) and augment
@@ -106,6 +176,20 @@ impl <T: SigningTypes> SignedPayload<T> for Payload<T::Public> {
 	}
 }
 
+/// A price signed by an oracle for the block its offchain worker ran at.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PricePayload<Public, BlockNumber> {
+	pub price: Price,
+	pub block_number: BlockNumber,
+	pub public: Public,
+}
+
+impl <T: SigningTypes> SignedPayload<T> for PricePayload<T::Public, T::BlockNumber> {
+	fn public(&self) -> T::Public {
+		self.public.clone()
+	}
+}
+
 // ref: https://serde.rs/container-attrs.html#crate
 #[derive(Deserialize, Encode, Decode, Default)]
 struct GithubInfo {
@@ -117,17 +201,6 @@ struct GithubInfo {
 	public_repos: u32,
 }
 
-#[derive(Deserialize, Encode, Decode, Default)]
-struct Coincap {
-	data: AssetInfo,
-}
-
-#[derive(Deserialize, Encode, Decode, Default)]
-struct AssetInfo {
-	#[serde(rename(deserialize = "priceUsd"), deserialize_with = "de_string_to_integer")]
-	price_usd: u32,
-}
-
 pub fn de_string_to_bytes<'de, D>(de: D) -> Result<Vec<u8>, D::Error>
 where
 	D: Deserializer<'de>,
@@ -136,14 +209,6 @@ where
 	Ok(s.as_bytes().to_vec())
 }
 
-pub fn de_string_to_integer<'de, D>(de: D) -> Result<u32, D::Error>
-where
-	D: Deserializer<'de>,
-{
-	let s: &str = Deserialize::deserialize(de)?;
-	Ok(s.parse::<f64>().unwrap() as u32)
-}
-
 impl fmt::Debug for GithubInfo {
 	// `fmt` converts the vector of bytes inside the struct back to string for
 	//   more friendly display.
@@ -158,6 +223,16 @@ impl fmt::Debug for GithubInfo {
 	}
 }
 
+/// Text written by `fmt::write`.
+struct DecimalText(Vec<u8>);
+
+impl fmt::Write for DecimalText {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.extend_from_slice(s.as_bytes());
+		Ok(())
+	}
+}
+
 /// This is the pallet's configuration trait
 pub trait Trait: system::Trait + CreateSignedTransaction<Call<Self>> {
 	/// The identifier type for an offchain worker.
@@ -166,13 +241,56 @@ pub trait Trait: system::Trait + CreateSignedTransaction<Call<Self>> {
 	type Call: From<Call<Self>>;
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Maximum number of feeds.
+	type MaxFeeds: Get<u32>;
+	/// Maximum encoded length of a feed.
+	type MaxFeedLength: Get<u32>;
+	/// Prices further than this from the median of all the sources are dropped.
+	type MaxPriceDeviation: Get<Permill>;
+	/// Minimum number of sources agreeing on the price to submit it.
+	type MinPriceSources: Get<u32>;
+	/// Maximum number of oracles.
+	type MaxOracles: Get<u32>;
+	/// Time of the rounds.
+	type UnixTime: UnixTime;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Example {
 		/// A vector of recently submitted numbers. Bounded by NUM_VEC_LEN
 		Numbers get(fn numbers): VecDeque<u32>;
-		Prices get(fn prices): VecDeque<u32>;
+		/// Finalized prices of the recent rounds. Bounded by PRICES_VEC_LEN
+		Prices get(fn prices): VecDeque<Price>;
+		/// Prices submitted in the current block, one per oracle. Sorted by oracle.
+		Submissions get(fn submissions): Vec<(T::AccountId, Price)>;
+		/// The last finalized round.
+		LatestRound get(fn latest_round): Option<RoundPriceOf<T>>;
+		/// Storage layout version, used to run the migrations.
+		StorageVersion get(fn storage_version) build(|_| Releases::V2FixedPrices): Releases;
+
+		/// Price feeds queried by the offchain worker, managed by Root.
+		Feeds get(fn feed): map hasher(twox_64_concat) FeedId => Option<FeedOf<T>>;
+		/// Id of the next added feed, ids are never reused.
+		NextFeedId get(fn next_feed_id): FeedId;
+
+		/// Accounts allowed to submit prices, managed by Root. Sorted.
+		Oracles get(fn oracles) build(|config: &GenesisConfig<T>| {
+			let mut oracles = config.oracles.clone();
+			oracles.sort();
+			oracles.dedup();
+			oracles
+		}): Vec<T::AccountId>;
+	}
+	add_extra_genesis {
+		config(oracles): Vec<T::AccountId>;
+		/// (url, headers, json_path, decimals, interval) of the initial feeds
+		config(feeds): Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>, u8, T::BlockNumber)>;
+		build(|config| {
+			for (url, headers, json_path, decimals, interval) in config.feeds.iter().cloned() {
+				<Module<T>>::insert_feed(Feed { url, headers, json_path, decimals, interval })
+					.expect("Genesis feeds are valid; qed");
+			}
+		});
 	}
 }
 
@@ -184,7 +302,19 @@ decl_event!(
 	{
 		/// Event generated when a new number is accepted to contribute to the average.
 		NewNumber(Option<AccountId>, u32),
-		NewPrice(Option<AccountId>, u32),
+		NewPrice(Option<AccountId>, Price),
+		/// A feed is added by Root.
+		FeedAdded(FeedId),
+		/// A feed is replaced by Root.
+		FeedUpdated(FeedId),
+		/// A feed is removed by Root.
+		FeedRemoved(FeedId),
+		/// An account is allowed to submit prices by Root.
+		OracleAdded(AccountId),
+		/// An account is not allowed to submit prices anymore.
+		OracleRemoved(AccountId),
+		/// The median of the submitted prices is finalized. (round, price, participants)
+		PriceFinalized(RoundId, Price, u32),
 	}
 );
 
@@ -209,6 +339,25 @@ decl_error! {
 		HttpRequestError,
 		HttpBodyConvertError,
 		FetchingPriceError,
+
+		// Error returned when too few price sources agree with each other
+		NotEnoughPriceSources,
+
+		// Errors returned when managing the feeds
+		TooManyFeeds,
+		FeedTooLong,
+		// The url, headers or json path of a feed are not UTF8, the json path is empty or
+		// the feed has more than `PRICE_DECIMALS` decimals
+		InvalidFeed,
+		UnknownFeed,
+
+		// Errors returned when managing the oracles, or when a price is not submitted by one
+		TooManyOracles,
+		AlreadyOracle,
+		NotOracle,
+
+		// Error returned when a signed price is older than `UNSIGNED_TXS_LONGEVITY` blocks
+		StalePrice,
 	}
 }
 
@@ -252,19 +401,111 @@ decl_module! {
 		}
 
 		#[weight = 10000]
-		pub fn submit_price_signed(origin, price: u32) -> DispatchResult {
+		pub fn submit_price_signed(origin, price: Price) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			debug::info!("submit_price_signed: ({}, {:?})", price, who);
-			Self::append_or_replace_price(price);
+			ensure!(Self::is_oracle(&who), Error::<T>::NotOracle);
+			debug::info!("submit_price_signed: ({:?}, {:?})", price, who);
+			Self::submit_price(who.clone(), price);
 
 			Self::deposit_event(RawEvent::NewPrice(Some(who), price));
 			Ok(())
 		}
 
+		#[weight = 10000]
+		pub fn submit_price_unsigned_with_signed_payload(origin,
+			payload: PricePayload<T::Public, T::BlockNumber>, _signature: T::Signature) -> DispatchResult
+		{
+			let _ = ensure_none(origin)?;
+			// The signature is verified in `validate_unsigned`, the oracle and the freshness are
+			//   checked again as they may have changed since the transaction entered the pool.
+			let PricePayload { price, block_number, public } = payload;
+			let who = public.into_account();
+			ensure!(Self::is_oracle(&who), Error::<T>::NotOracle);
+			ensure!(!Self::is_stale(block_number), Error::<T>::StalePrice);
+			debug::info!("submit_price_unsigned_with_signed_payload: ({:?}, {:?})", price, who);
+			Self::submit_price(who.clone(), price);
+
+			Self::deposit_event(RawEvent::NewPrice(Some(who), price));
+			Ok(())
+		}
+
+		#[weight = 10000]
+		pub fn add_oracle(origin, oracle: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			let mut oracles = Self::oracles();
+			ensure!((oracles.len() as u32) < T::MaxOracles::get(), Error::<T>::TooManyOracles);
+			match oracles.binary_search(&oracle) {
+				Ok(_) => return Err(Error::<T>::AlreadyOracle.into()),
+				Err(index) => oracles.insert(index, oracle.clone()),
+			}
+			Oracles::<T>::put(oracles);
+
+			Self::deposit_event(RawEvent::OracleAdded(oracle));
+			Ok(())
+		}
+
+		#[weight = 10000]
+		pub fn remove_oracle(origin, oracle: T::AccountId) -> DispatchResult {
+			ensure_root(origin)?;
+			let mut oracles = Self::oracles();
+			let index = oracles.binary_search(&oracle).map_err(|_| Error::<T>::NotOracle)?;
+			oracles.remove(index);
+			Oracles::<T>::put(oracles);
+
+			Self::deposit_event(RawEvent::OracleRemoved(oracle));
+			Ok(())
+		}
+
+		#[weight = 10000]
+		pub fn add_feed(origin, feed: FeedOf<T>) -> DispatchResult {
+			ensure_root(origin)?;
+			let feed_id = Self::insert_feed(feed)?;
+
+			Self::deposit_event(RawEvent::FeedAdded(feed_id));
+			Ok(())
+		}
+
+		#[weight = 10000]
+		pub fn update_feed(origin, feed_id: FeedId, feed: FeedOf<T>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(Feeds::<T>::contains_key(feed_id), Error::<T>::UnknownFeed);
+			Self::ensure_valid_feed(&feed)?;
+			Feeds::<T>::insert(feed_id, feed);
+
+			Self::deposit_event(RawEvent::FeedUpdated(feed_id));
+			Ok(())
+		}
+
+		#[weight = 10000]
+		pub fn remove_feed(origin, feed_id: FeedId) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(Feeds::<T>::contains_key(feed_id), Error::<T>::UnknownFeed);
+			Feeds::<T>::remove(feed_id);
+
+			Self::deposit_event(RawEvent::FeedRemoved(feed_id));
+			Ok(())
+		}
+
+		fn on_initialize(_now: T::BlockNumber) -> Weight {
+			// `on_finalize` reads the submissions and writes the round
+			T::DbWeight::get().reads_writes(2, 3)
+		}
+
+		fn on_finalize(now: T::BlockNumber) {
+			Self::finalize_round(now);
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			if Self::storage_version() != Releases::V1IntegerPrices {
+				return 0;
+			}
+			Self::migrate_to_fixed_prices()
+		}
+
 		fn offchain_worker(block_number: T::BlockNumber) {
 			debug::info!("Entering off-chain worker");
 
-			let result = Self::fetch_price_and_signed();
+			let result = Self::fetch_price_and_submit(block_number);
 
 			if let Err(e) = result {
 				debug::error!("offchain_worker error: {:?}", e);
@@ -309,7 +550,41 @@ impl<T: Trait> Module<T> {
 		});
 	}
 
-	fn append_or_replace_price(price: u32) {
+	/// Record the price of an oracle for the current round, replacing its previous one.
+	fn submit_price(oracle: T::AccountId, price: Price) {
+		Submissions::<T>::mutate(|submissions| {
+			match submissions.binary_search_by(|(account, _)| account.cmp(&oracle)) {
+				Ok(index) => submissions[index].1 = price,
+				Err(index) => submissions.insert(index, (oracle, price)),
+			}
+		});
+	}
+
+	/// Finalize the median of the submitted prices, if any.
+	fn finalize_round(now: T::BlockNumber) {
+		let mut prices = Submissions::<T>::take().into_iter()
+			.map(|(_, price)| price)
+			.collect::<Vec<_>>();
+		let participants = prices.len() as u32;
+		let price = match Self::median(&mut prices) {
+			Some(price) => price,
+			None => return,
+		};
+
+		let round = Self::latest_round().map_or(0, |latest| latest.round + 1);
+		LatestRound::<T>::put(RoundPrice {
+			round,
+			price,
+			block_number: now,
+			timestamp: T::UnixTime::now().as_millis() as u64,
+			participants,
+		});
+		Self::append_or_replace_price(price);
+
+		Self::deposit_event(RawEvent::PriceFinalized(round, price, participants));
+	}
+
+	fn append_or_replace_price(price: Price) {
 		Prices::mutate(|prices| {
 			if prices.len() == PRICES_VEC_LEN {
 				let _ = prices.pop_front();
@@ -319,51 +594,283 @@ impl<T: Trait> Module<T> {
 		});
 	}	
 
-	fn fetch_price_and_signed() -> Result<(), Error<T>> {
-		debug::info!("fetch price and signed");
-		let signer = Signer::<T, T::AuthorityId>::any_account();
+	/// Convert the `u32` prices of the old storage to `Price` and bump the storage version.
+	///   The offchain price cache of the feeds does not decode anymore, so every feed is
+	///   queried again.
+	fn migrate_to_fixed_prices() -> Weight {
+		let _ = Prices::translate::<VecDeque<u32>, _>(|prices| {
+			prices.map(|prices| prices.into_iter().map(Price::saturating_from_integer).collect())
+		});
+		StorageVersion::put(Releases::V2FixedPrices);
 
-		if !signer.can_sign() {
+		T::DbWeight::get().reads_writes(2, 2)
+	}
+
+	/// Add a feed under the next feed id.
+	fn insert_feed(feed: FeedOf<T>) -> Result<FeedId, Error<T>> {
+		ensure!((Feeds::<T>::iter().count() as u32) < T::MaxFeeds::get(), Error::<T>::TooManyFeeds);
+		Self::ensure_valid_feed(&feed)?;
+
+		let feed_id = Self::next_feed_id();
+		NextFeedId::put(feed_id + 1);
+		Feeds::<T>::insert(feed_id, feed);
+		Ok(feed_id)
+	}
+
+	/// Check that the offchain worker can use a feed.
+	fn ensure_valid_feed(feed: &FeedOf<T>) -> Result<(), Error<T>> {
+		ensure!(feed.encoded_size() as u32 <= T::MaxFeedLength::get(), Error::<T>::FeedTooLong);
+		ensure!(!feed.json_path.is_empty(), Error::<T>::InvalidFeed);
+		ensure!(feed.decimals as u32 <= PRICE_DECIMALS, Error::<T>::InvalidFeed);
+
+		let texts = sp_std::iter::once(&feed.url)
+			.chain(feed.headers.iter().flat_map(|(name, value)| vec![name, value]))
+			.chain(feed.json_path.iter());
+		for text in texts {
+			ensure!(str::from_utf8(text).is_ok(), Error::<T>::InvalidFeed);
+		}
+		Ok(())
+	}
+
+	/// Whether `who` is allowed to submit prices.
+	pub fn is_oracle(who: &T::AccountId) -> bool {
+		Self::oracles().binary_search(who).is_ok()
+	}
+
+	/// Whether a price signed for `block_number` is too old to be submitted, so a payload
+	///   can not be replayed once it left the pool.
+	fn is_stale(block_number: T::BlockNumber) -> bool {
+		block_number.saturating_add(UNSIGNED_TXS_LONGEVITY.into()) < <system::Module<T>>::block_number()
+	}
+
+	/// Keys of the local keystore whose accounts are oracles.
+	fn local_oracle_keys() -> Vec<T::Public> {
+		let oracles = Self::oracles();
+		<T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+			.into_iter()
+			.map(|key| <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic::from(key).into())
+			.filter(|public: &T::Public| oracles.binary_search(&public.clone().into_account()).is_ok())
+			.collect()
+	}
+
+	fn fetch_price_and_submit(block_number: T::BlockNumber) -> Result<(), Error<T>> {
+		debug::info!("fetch price and submit");
+		let keys = Self::local_oracle_keys();
+
+		if keys.is_empty() {
+			debug::error!("No local oracle account available");
 			return Err(<Error<T>>::NoLocalAcctForSigning);
 		}
 
-		let price: u32 = Self::fetch_price_coincap().map_err(|_| <Error<T>>::FetchingPriceError)?;
-		debug::info!("fetch price: {}", price);
+		// A price is submitted when at least one feed is queried, each one at its own interval
+		if Self::fetch_due_feeds(block_number) == 0 {
+			return Ok(());
+		}
 
-		let result = signer.send_signed_transaction(|_acct|
-			// This is the on-chain function
-			Call::submit_price_signed(price)
-		);
+		let prices = Self::cached_prices(block_number);
+		let price = Self::aggregate_prices(prices).ok_or(<Error<T>>::NotEnoughPriceSources)?;
+		debug::info!("fetch price: {:?}", price);
 
-		// Display error if the signed tx fails.
-		if let Some((acc, res)) = result {
+		// Oracles submit unsigned transactions and pay no fee, each local oracle key signs the price
+		let results = Signer::<T, T::AuthorityId>::all_accounts()
+			.with_filter(keys)
+			.send_unsigned_transaction(
+				|acct| PricePayload { price, block_number, public: acct.public.clone() },
+				Call::submit_price_unsigned_with_signed_payload
+			);
+
+		for (acc, res) in results {
 			if res.is_err() {
-				debug::error!("failure: offchain_signed_tx: tx sent: {:?}", acc.id);
-				return Err(<Error<T>>::OffchainSignedTxError);
+				debug::error!("failure: submit price: tx sent: {:?}", acc.id);
+				return Err(<Error<T>>::OffchainUnsignedTxSignedPayloadError);
 			}
-			// Transaction is sent successfully
-			return Ok(());
 		}
 
 		Ok(())
 	}
 
-	fn fetch_price_coincap() -> Result<u32, Error::<T>> {
-		debug::info!("fetch coincap");
+	/// Offchain storage key of the last query of a feed.
+	fn feed_cache_key(feed_id: FeedId) -> Vec<u8> {
+		(b"offchain-demo::feed", feed_id).encode()
+	}
 
-		let user_agent: &str = "";
-		let body = Self::fetch_from_remote(COINCAP_REMOTE_URL, user_agent).map_err(|_| Error::<T>::HttpRequestError)?;
+	/// Query the feeds due at `block_number` at once and cache their prices.
+	///   Returns the number of feeds queried.
+	fn fetch_due_feeds(block_number: T::BlockNumber) -> usize {
+		let due = Feeds::<T>::iter()
+			.map(|(feed_id, feed)| (feed_id, T::Hashing::hash_of(&feed), feed))
+			.filter(|(feed_id, feed_hash, feed)| {
+				let cache = StorageValueRef::persistent(&Self::feed_cache_key(*feed_id));
+				match cache.get::<(T::Hash, T::BlockNumber, Option<Price>)>() {
+					// A feed updated by Root is queried again
+					Some(Some((hash, fetched_at, _))) =>
+						hash != *feed_hash || block_number >= fetched_at.saturating_add(feed.interval),
+					_ => true,
+				}
+			})
+			.collect::<Vec<_>>();
 
-		let body_str = sp_std::str::from_utf8(&body).map_err(|_| {
-			debug::warn!("Not UTF8 body");
-			Error::<T>::HttpBodyConvertError
-		})?;
+		let deadline = sp_io::offchain::timestamp()
+			.add(rt_offchain::Duration::from_millis(FETCH_TIMEOUT_PERIOD));
 
-		let price_info: Coincap = serde_json::from_str(&body_str).unwrap();
+		let (sent, requests): (Vec<_>, Vec<_>) = due.iter()
+			.enumerate()
+			.filter_map(|(index, (feed_id, _, feed))| match Self::send_feed_request(feed, deadline) {
+				Some(request) => Some((index, request)),
+				None => {
+					debug::warn!("Failed to send the request of feed {}", feed_id);
+					None
+				},
+			})
+			.unzip();
+
+		// The requests run concurrently, a slow feed does not delay the others
+		let responses = rt_offchain::http::PendingRequest::try_wait_all(requests, deadline);
+
+		let mut prices = vec![None; due.len()];
+		for (index, response) in sent.into_iter().zip(responses) {
+			let feed = &due[index].2;
+			prices[index] = match response {
+				Ok(Ok(response)) if response.code == 200 => Self::parse_price(
+					&response.body().collect::<Vec<u8>>(), &feed.json_path, feed.decimals,
+				),
+				_ => None,
+			};
+		}
+
+		// Failed feeds are cached too, they wait for their next interval
+		for ((feed_id, feed_hash, _), price) in due.iter().zip(prices) {
+			match price {
+				Some(price) => debug::info!("price from feed {}: {:?}", feed_id, price),
+				None => debug::warn!("No price from feed {}", feed_id),
+			}
+			StorageValueRef::persistent(&Self::feed_cache_key(*feed_id)).set(&(feed_hash, block_number, price));
+		}
+
+		due.len()
+	}
+
+	/// Send the HTTP request of a feed with its headers.
+	fn send_feed_request(
+		feed: &FeedOf<T>,
+		deadline: rt_offchain::Timestamp,
+	) -> Option<rt_offchain::http::PendingRequest> {
+		let mut request = rt_offchain::http::Request::get(str::from_utf8(&feed.url).ok()?);
+		for (name, value) in feed.headers.iter() {
+			request = request.add_header(str::from_utf8(name).ok()?, str::from_utf8(value).ok()?);
+		}
+		request.deadline(deadline).send().ok()
+	}
+
+	/// The cached prices of the current feeds, fetched within their interval before `block_number`.
+	fn cached_prices(block_number: T::BlockNumber) -> Vec<Price> {
+		Feeds::<T>::iter()
+			.filter_map(|(feed_id, feed)| {
+				let cache = StorageValueRef::persistent(&Self::feed_cache_key(feed_id));
+				match cache.get::<(T::Hash, T::BlockNumber, Option<Price>)>() {
+					Some(Some((hash, fetched_at, price)))
+						if hash == T::Hashing::hash_of(&feed)
+							&& block_number.saturating_sub(fetched_at) <= feed.interval => price,
+					_ => None,
+				}
+			})
+			.collect()
+	}
+
+	/// Read the price at `json_path` of a JSON response, given with `decimals` decimals.
+	fn parse_price(body: &[u8], json_path: &[Vec<u8>], decimals: u8) -> Option<Price> {
+		let json: serde_json::Value = serde_json::from_slice(body).ok()?;
+		let value = json_path.iter().try_fold(&json, |value, key| value.get(str::from_utf8(key).ok()?))?;
+
+		match value {
+			serde_json::Value::String(price) => Self::parse_decimal(price.as_bytes(), decimals),
+			// Numbers are written back as they are in the response, e.g. `4.25` or `425e-2`
+			serde_json::Value::Number(price) => {
+				let mut text = DecimalText(Vec::new());
+				fmt::write(&mut text, format_args!("{}", price)).ok()?;
+				Self::parse_number(&text.0, decimals)
+			},
+			_ => None,
+		}
+	}
+
+	/// Parse a non-negative decimal number without exponent, e.g. `4.25`, given with
+	///   `decimals` decimals. Digits beyond `PRICE_DECIMALS` are truncated.
+	fn parse_decimal(text: &[u8], decimals: u8) -> Option<Price> {
+		if decimals as u32 > PRICE_DECIMALS {
+			return None;
+		}
+		Self::parse_shifted(text, decimals as i32)
+	}
+
+	/// Parse a non-negative JSON number, with an optional exponent, e.g. `4.25` or `425e-2`,
+	///   given with `decimals` decimals. Digits beyond `PRICE_DECIMALS` are truncated.
+	fn parse_number(text: &[u8], decimals: u8) -> Option<Price> {
+		if decimals as u32 > PRICE_DECIMALS {
+			return None;
+		}
 
-		debug::warn!("Submit Signed: Got price: {} ", price_info.data.price_usd);
+		let (mantissa, exponent) = match text.iter().position(|&c| c == b'e' || c == b'E') {
+			Some(e) => (&text[..e], str::from_utf8(&text[e + 1..]).ok()?.parse::<i32>().ok()?),
+			None => (text, 0),
+		};
+		Self::parse_shifted(mantissa, (decimals as i32).checked_sub(exponent)?)
+	}
+
+	/// Parse a non-negative decimal number without exponent, divided by 10^`shift`.
+	///   Digits beyond `PRICE_DECIMALS` are truncated.
+	fn parse_shifted(text: &[u8], shift: i32) -> Option<Price> {
+		let (integer, fraction) = match text.iter().position(|&c| c == b'.') {
+			Some(point) if point + 1 < text.len() => (&text[..point], &text[point + 1..]),
+			Some(_) => return None,
+			None => (text, &[][..]),
+		};
+		if integer.is_empty() || !integer.iter().chain(fraction).all(u8::is_ascii_digit) {
+			return None;
+		}
+
+		// The digits are the inner value of the price times 10^-`scale`
+		let digits = integer.len() + fraction.len();
+		let scale = (PRICE_DECIMALS as i32).checked_sub(shift)?.checked_sub(fraction.len() as i32)?;
+		let kept = (digits as i64 + scale.min(0) as i64).max(0) as usize;
+		let mut inner = 0u128;
+		for digit in integer.iter().chain(fraction).take(kept) {
+			inner = inner.checked_mul(10)?.checked_add((digit - b'0') as u128)?;
+		}
+		inner.checked_mul(10u128.checked_pow(scale.max(0) as u32)?).map(Price::from_inner)
+	}
 
-		Ok(price_info.data.price_usd)
+	/// The median of the prices within `MaxPriceDeviation` of the median of all the prices.
+	///   None if fewer than `MinPriceSources` prices are left.
+	fn aggregate_prices(mut prices: Vec<Price>) -> Option<Price> {
+		let median = Self::median(&mut prices)?;
+		let max_distance = Price::from_inner(T::MaxPriceDeviation::get() * median.into_inner());
+
+		let mut agreeing = prices.into_iter()
+			.filter(|&price| price.max(median) - price.min(median) <= max_distance)
+			.collect::<Vec<_>>();
+		if (agreeing.len() as u32) < T::MinPriceSources::get() {
+			debug::warn!("Only {} price sources agree on {:?}", agreeing.len(), median);
+			return None;
+		}
+
+		Self::median(&mut agreeing)
+	}
+
+	/// The middle price, or the mean of the two middle prices of an even number of prices.
+	fn median(prices: &mut Vec<Price>) -> Option<Price> {
+		if prices.is_empty() {
+			return None;
+		}
+		prices.sort();
+
+		let mid = prices.len() / 2;
+		if prices.len() % 2 == 0 {
+			let (low, high) = (prices[mid - 1].into_inner(), prices[mid].into_inner());
+			Some(Price::from_inner(low + (high - low) / 2))
+		} else {
+			Some(prices[mid])
+		}
 	}
 
 	/// Check if we have fetched github info before. If yes, we can use the cached version
@@ -553,7 +1060,7 @@ impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
 		let valid_tx = |provide| ValidTransaction::with_tag_prefix("ocw-demo")
 			.priority(UNSIGNED_TXS_PRIORITY)
 			.and_provides([&provide])
-			.longevity(3)
+			.longevity(UNSIGNED_TXS_LONGEVITY as u64)
 			.propagate(true)
 			.build();
 
@@ -565,11 +1072,37 @@ impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
 				}
 				valid_tx(b"submit_number_unsigned_with_signed_payload".to_vec())
 			},
+			Call::submit_price_unsigned_with_signed_payload(ref payload, ref signature) => {
+				if !SignedPayload::<T>::verify::<T::AuthorityId>(payload, signature.clone()) {
+					return InvalidTransaction::BadProof.into();
+				}
+				// Only oracles enter the pool
+				let oracle = payload.public.clone().into_account();
+				if !Self::is_oracle(&oracle) {
+					return InvalidTransaction::BadSigner.into();
+				}
+				if payload.block_number > <system::Module<T>>::block_number() {
+					return InvalidTransaction::Future.into();
+				}
+				if Self::is_stale(payload.block_number) {
+					return InvalidTransaction::Stale.into();
+				}
+				// One price per oracle and block
+				valid_tx((b"submit_price_unsigned_with_signed_payload", oracle, payload.block_number).encode())
+			},
 			_ => InvalidTransaction::Call.into(),
 		}
 	}
 }
 
+impl<T: Trait> PriceProvider<T::BlockNumber> for Module<T> {
+	fn latest_price() -> Option<(Price, T::BlockNumber)> {
+		Self::latest_round().map(|latest| {
+			(latest.price, <system::Module<T>>::block_number().saturating_sub(latest.block_number))
+		})
+	}
+}
+
 impl<T: Trait> rt_offchain::storage_lock::BlockNumberProvider for Module<T> {
 	type BlockNumber = T::BlockNumber;
 	fn current_block_number() -> Self::BlockNumber {
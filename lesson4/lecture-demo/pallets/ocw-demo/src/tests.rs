@@ -1,5 +1,9 @@
 use crate::*;
-use frame_support::{assert_ok, impl_outer_event, impl_outer_origin, parameter_types};
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+	traits::{OnFinalize, UnixTime},
+	unsigned::ValidateUnsigned,
+};
 use parity_scale_codec::{alloc::sync::Arc, Decode};
 use parking_lot::RwLock;
 use sp_core::{
@@ -13,10 +17,11 @@ use sp_core::{
 	H256,
 };
 use sp_io::TestExternalities;
+use core::time::Duration;
 use sp_runtime::{
 	testing::{Header, TestXt},
-	traits::{BlakeTwo256, IdentityLookup, Verify},
-	Perbill,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup, Verify},
+	FixedPointNumber, Perbill, Permill,
 };
 
 use crate as ocw_demo;
@@ -77,12 +82,32 @@ type TestExtrinsic = TestXt<Call<TestRuntime>, ()>;
 
 parameter_types! {
 	pub const UnsignedPriority: u64 = 100;
+	pub const MaxFeeds: u32 = 3;
+	pub const MaxFeedLength: u32 = 100;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(10);
+	pub const MinPriceSources: u32 = 2;
+	pub const MaxOracles: u32 = 2;
+}
+
+// Blocks are 6 seconds apart
+pub struct BlockTime;
+
+impl UnixTime for BlockTime {
+	fn now() -> Duration {
+		Duration::from_secs(System::block_number() * 6)
+	}
 }
 
 impl Trait for TestRuntime {
 	type AuthorityId = crypto::TestAuthId;
 	type Call = Call<TestRuntime>;
 	type Event = TestEvent;
+	type MaxFeeds = MaxFeeds;
+	type MaxFeedLength = MaxFeedLength;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MinPriceSources = MinPriceSources;
+	type MaxOracles = MaxOracles;
+	type UnixTime = BlockTime;
 }
 
 impl<LocalCall> system::offchain::CreateSignedTransaction<LocalCall> for TestRuntime
@@ -213,3 +238,474 @@ fn test_offchain_unsigned_tx() {
 		assert_eq!(tx.call, Call::submit_number_unsigned(num));
 	});
 }
+
+fn price_response(state: &mut OffchainState, uri: &str, body: &[u8]) {
+	state.expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: uri.into(),
+		response: Some(body.to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+fn price(integer: u32) -> Price {
+	Price::saturating_from_integer(integer)
+}
+
+fn cents(cents: u32) -> Price {
+	Price::saturating_from_rational(cents, 100)
+}
+
+fn feed(url: &str, json_path: &[&str], interval: u64) -> FeedOf<TestRuntime> {
+	Feed {
+		url: url.into(),
+		headers: vec![],
+		json_path: json_path.iter().map(|key| key.as_bytes().to_vec()).collect(),
+		decimals: 0,
+		interval,
+	}
+}
+
+// The account of the keystore key
+fn local_oracle() -> sr25519::Public {
+	sp_io::crypto::sr25519_public_keys(KEY_TYPE)[0]
+}
+
+fn signed_price(price: Price, block_number: u64) -> Call<TestRuntime> {
+	let payload = PricePayload { price, block_number, public: local_oracle() };
+	let signature = sp_io::crypto::sr25519_sign(KEY_TYPE, &local_oracle(), &payload.encode()).unwrap();
+	Call::submit_price_unsigned_with_signed_payload(payload, signature)
+}
+
+// The price of the last transaction in the pool
+fn submitted_price(pool_state: &RwLock<PoolState>) -> Price {
+	let tx = pool_state.write().transactions.pop().unwrap();
+	let tx = TestExtrinsic::decode(&mut &*tx).unwrap();
+	assert_eq!(tx.signature, None);
+	match tx.call {
+		Call::submit_price_unsigned_with_signed_payload(payload, signature) => {
+			assert!(SignedPayload::<TestRuntime>::verify::<crypto::TestAuthId>(&payload, signature));
+			assert_eq!(payload.public, local_oracle());
+			payload.price
+		},
+		call => panic!("Unexpected call {:?}", call),
+	}
+}
+
+// Feeds 0, 1 and 2 are queried every 10 blocks
+fn add_test_feeds() {
+	assert_ok!(OcwDemo::add_feed(Origin::root(), feed("https://a.test/dot", &["data", "priceUsd"], 10)));
+	assert_ok!(OcwDemo::add_feed(Origin::root(), feed("https://b.test/dot", &["polkadot", "usd"], 10)));
+	assert_ok!(OcwDemo::add_feed(Origin::root(), feed("https://c.test/dot", &["price"], 10)));
+}
+
+#[test]
+fn root_manages_feeds() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let acct: <TestRuntime as system::Trait>::AccountId = Default::default();
+		assert_noop!(OcwDemo::add_feed(Origin::signed(acct), feed("https://a.test/dot", &["price"], 10)), BadOrigin);
+		add_test_feeds();
+		assert_eq!(OcwDemo::feed(1), Some(feed("https://b.test/dot", &["polkadot", "usd"], 10)));
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::FeedAdded(2))));
+
+		assert_noop!(
+			OcwDemo::add_feed(Origin::root(), feed("https://d.test/dot", &["price"], 10)),
+			Error::<TestRuntime>::TooManyFeeds
+		);
+
+		assert_noop!(OcwDemo::update_feed(Origin::signed(acct), 1, feed("https://d.test/dot", &["price"], 5)), BadOrigin);
+		assert_ok!(OcwDemo::update_feed(Origin::root(), 1, feed("https://d.test/dot", &["price"], 5)));
+		assert_eq!(OcwDemo::feed(1), Some(feed("https://d.test/dot", &["price"], 5)));
+
+		assert_noop!(OcwDemo::remove_feed(Origin::signed(acct), 1), BadOrigin);
+		assert_ok!(OcwDemo::remove_feed(Origin::root(), 1));
+		assert_eq!(OcwDemo::feed(1), None);
+		assert_noop!(
+			OcwDemo::remove_feed(Origin::root(), 1),
+			Error::<TestRuntime>::UnknownFeed
+		);
+
+		// Feed ids are not reused
+		assert_ok!(OcwDemo::add_feed(Origin::root(), feed("https://e.test/dot", &["price"], 10)));
+		assert_eq!(OcwDemo::feed(3), Some(feed("https://e.test/dot", &["price"], 10)));
+	});
+}
+
+#[test]
+fn invalid_feeds_are_rejected() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let invalid_feeds = vec![
+			(feed("https://a.test/dot", &[], 10), Error::<TestRuntime>::InvalidFeed),
+			(Feed { url: vec![0xff, 0xfe], ..feed("", &["price"], 10) }, Error::<TestRuntime>::InvalidFeed),
+			(feed(&"a".repeat(100), &["price"], 10), Error::<TestRuntime>::FeedTooLong),
+		];
+		for (invalid_feed, error) in invalid_feeds {
+			assert_noop!(
+				OcwDemo::add_feed(Origin::root(), invalid_feed),
+				error
+			);
+		}
+		assert_eq!(OcwDemo::next_feed_id(), 0);
+	});
+}
+
+#[test]
+fn parse_price_follows_json_path() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let path = |keys: &[&str]| feed("", keys, 0).json_path;
+		assert_eq!(OcwDemo::parse_price(br#"{"data": {"priceUsd": "4.25"}}"#, &path(&["data", "priceUsd"]), 0), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_price(br#"{"polkadot": {"usd": 5}}"#, &path(&["polkadot", "usd"]), 0), Some(price(5)));
+		assert_eq!(OcwDemo::parse_price(br#"{"polkadot": {"usd": 4.25}}"#, &path(&["polkadot", "usd"]), 0), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_price(br#"{"price": 425}"#, &path(&["price"]), 2), Some(cents(425)));
+		// Numbers in exponent form are exact too
+		assert_eq!(OcwDemo::parse_price(br#"{"price": 1e-5}"#, &path(&["price"]), 0), Some(Price::from_inner(10_000_000_000_000)));
+		assert_eq!(OcwDemo::parse_price(br#"{"price": 4.25E2}"#, &path(&["price"]), 2), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_price(br#"{"price": 0.1}"#, &path(&["price"]), 0), Some(Price::from_inner(100_000_000_000_000_000)));
+		assert_eq!(OcwDemo::parse_price(br#"{"price": -1}"#, &path(&["price"]), 0), None);
+		assert_eq!(OcwDemo::parse_price(br#"{"price": "abc"}"#, &path(&["price"]), 0), None);
+		assert_eq!(OcwDemo::parse_price(br#"{"price": "-1"}"#, &path(&["price"]), 0), None);
+		assert_eq!(OcwDemo::parse_price(br#"{"data": {}}"#, &path(&["data", "priceUsd"]), 0), None);
+		assert_eq!(OcwDemo::parse_price(b"not json", &path(&["price"]), 0), None);
+	});
+}
+
+#[test]
+fn parse_decimal_is_exact() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_eq!(10u128.pow(PRICE_DECIMALS), Price::accuracy());
+		assert_eq!(OcwDemo::parse_decimal(b"4.25", 0), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_decimal(b"0.1", 0), Some(Price::from_inner(100_000_000_000_000_000)));
+		assert_eq!(OcwDemo::parse_decimal(b"425", 2), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_decimal(b"42.5", 1), Some(cents(425)));
+		// Digits beyond the accuracy of a price are truncated
+		assert_eq!(OcwDemo::parse_decimal(b"1.0000000000000000019", 0), Some(Price::from_inner(1_000_000_000_000_000_001)));
+		assert_eq!(OcwDemo::parse_decimal(b"1", 18), Some(Price::from_inner(1)));
+		assert_eq!(OcwDemo::parse_decimal(b"1", 19), None);
+		// Overflow
+		assert_eq!(OcwDemo::parse_decimal(b"1000000000000000000000", 0), None);
+
+		for invalid in &[&b""[..], b".", b"1.", b".5", b"-1", b"+1", b"1e5", b"1.2.3", b"1,5", b" 1", b"abc"] {
+			assert_eq!(OcwDemo::parse_decimal(invalid, 0), None);
+		}
+	});
+}
+
+#[test]
+fn parse_number_reads_exponents() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_eq!(OcwDemo::parse_number(b"4.25", 0), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_number(b"1e-5", 0), Some(Price::from_inner(10_000_000_000_000)));
+		assert_eq!(OcwDemo::parse_number(b"425e-2", 0), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_number(b"4.25e+2", 2), Some(cents(425)));
+		assert_eq!(OcwDemo::parse_number(b"1E3", 0), Some(price(1000)));
+		// Digits beyond the accuracy of a price are truncated
+		assert_eq!(OcwDemo::parse_number(b"19e-19", 0), Some(Price::from_inner(1)));
+		assert_eq!(OcwDemo::parse_number(b"1e-30", 0), Some(Price::from_inner(0)));
+		// Overflow
+		assert_eq!(OcwDemo::parse_number(b"1e21", 0), None);
+		assert_eq!(OcwDemo::parse_number(b"1e2147483647", 0), None);
+
+		for invalid in &[&b"1e"[..], b"e5", b"1e5.5", b"1e--5", b"-1e5", b"1.e5"] {
+			assert_eq!(OcwDemo::parse_number(invalid, 0), None);
+		}
+	});
+}
+#[test]
+fn aggregate_prices_drops_outliers() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_eq!(OcwDemo::aggregate_prices(vec![price(100), price(150), price(102)]), Some(price(101)));
+		assert_eq!(OcwDemo::aggregate_prices(vec![price(100), price(104), price(102)]), Some(price(102)));
+		assert_eq!(OcwDemo::aggregate_prices(vec![cents(10001), cents(10002)]), Some(Price::saturating_from_rational(200_03, 200)));
+		// The median of all the prices is 125, both of them are too far from it
+		assert_eq!(OcwDemo::aggregate_prices(vec![price(100), price(150)]), None);
+		assert_eq!(OcwDemo::aggregate_prices(vec![price(100)]), None);
+		assert_eq!(OcwDemo::aggregate_prices(vec![]), None);
+	});
+}
+
+#[test]
+fn fetch_price_and_submit_submits_median_of_agreeing_sources() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	price_response(&mut offchain_state.write(), "https://a.test/dot", br#"{"data": {"priceUsd": "100.9"}}"#);
+	price_response(&mut offchain_state.write(), "https://b.test/dot", br#"{"polkadot": {"usd": 150}}"#);
+	price_response(&mut offchain_state.write(), "https://c.test/dot", br#"{"price": "102"}"#);
+
+	t.execute_with(|| {
+		add_test_feeds();
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		OcwDemo::fetch_price_and_submit(1).unwrap();
+
+		// The cents are kept
+		assert_eq!(submitted_price(&pool_state), Price::saturating_from_rational(101_45, 100));
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn fetch_price_and_submit_fails_without_enough_sources() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	price_response(&mut offchain_state.write(), "https://a.test/dot", br#"{"data": {"priceUsd": "100"}}"#);
+	price_response(&mut offchain_state.write(), "https://b.test/dot", br#"{"error": "rate limited"}"#);
+	price_response(&mut offchain_state.write(), "https://c.test/dot", br#"{"price": "150"}"#);
+
+	t.execute_with(|| {
+		add_test_feeds();
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		assert!(matches!(OcwDemo::fetch_price_and_submit(1), Err(Error::<TestRuntime>::NotEnoughPriceSources)));
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn fetch_price_and_submit_respects_feed_intervals() {
+	let (mut t, pool_state, offchain_state) = ExternalityBuilder::build();
+	price_response(&mut offchain_state.write(), "https://a.test/dot", br#"{"data": {"priceUsd": "100"}}"#);
+	price_response(&mut offchain_state.write(), "https://b.test/dot", br#"{"polkadot": {"usd": 102}}"#);
+	price_response(&mut offchain_state.write(), "https://c.test/dot", br#"{"price": "104"}"#);
+
+	t.execute_with(|| {
+		add_test_feeds();
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		OcwDemo::fetch_price_and_submit(1).unwrap();
+		assert_eq!(pool_state.write().transactions.len(), 1);
+		pool_state.write().transactions.clear();
+
+		// No feed is due before block 11, no request is expected by the offchain state
+		OcwDemo::fetch_price_and_submit(10).unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+
+		// An updated feed is queried again at once, along with the cached prices of the others
+		let mut headers_feed = feed("https://c.test/v2/dot", &["price"], 10);
+		headers_feed.headers = vec![(b"X-Api-Key".to_vec(), b"secret".to_vec())];
+		headers_feed.decimals = 1;
+		assert_ok!(OcwDemo::update_feed(Origin::root(), 2, headers_feed));
+		offchain_state.write().expect_request(testing::PendingRequest {
+			method: "GET".into(),
+			uri: "https://c.test/v2/dot".into(),
+			headers: vec![("X-Api-Key".into(), "secret".into())],
+			response: Some(br#"{"price": 1060}"#.to_vec()),
+			sent: true,
+			..Default::default()
+		});
+		OcwDemo::fetch_price_and_submit(10).unwrap();
+
+		assert_eq!(submitted_price(&pool_state), price(102));
+	});
+}
+
+#[test]
+fn cached_prices_ignores_stale_prices() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		add_test_feeds();
+		// Feed 0 is fetched at block 1, feed 1 at block 5 and feed 2 failed at block 5
+		for (feed_id, fetched_at, price) in vec![(0, 1u64, Some(price(100))), (1, 5, Some(price(102))), (2, 5, None)] {
+			let feed_hash = <TestRuntime as system::Trait>::Hashing::hash_of(&OcwDemo::feed(feed_id).unwrap());
+			StorageValueRef::persistent(&OcwDemo::feed_cache_key(feed_id)).set(&(feed_hash, fetched_at, price));
+		}
+		let cached_prices = |block_number| {
+			let mut prices = OcwDemo::cached_prices(block_number);
+			prices.sort();
+			prices
+		};
+
+		assert_eq!(cached_prices(11), vec![price(100), price(102)]);
+		// The price of feed 0 is older than its interval of 10 blocks
+		assert_eq!(cached_prices(12), vec![price(102)]);
+	});
+}
+
+#[test]
+fn fetch_price_and_submit_requires_a_local_oracle() {
+	let (mut t, pool_state, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		add_test_feeds();
+		// No feed is queried without an oracle key, no request is expected by the offchain state
+		assert!(matches!(OcwDemo::fetch_price_and_submit(1), Err(Error::<TestRuntime>::NoLocalAcctForSigning)));
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn root_manages_oracles() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let (oracle1, oracle2, oracle3) =
+			(sr25519::Public::from_raw([1; 32]), sr25519::Public::from_raw([2; 32]), sr25519::Public::from_raw([3; 32]));
+
+		assert_noop!(OcwDemo::add_oracle(Origin::signed(oracle1), oracle1), BadOrigin);
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), oracle2));
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), oracle1));
+		assert_eq!(OcwDemo::oracles(), vec![oracle1, oracle2]);
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::OracleAdded(oracle1))));
+
+		assert_noop!(OcwDemo::add_oracle(Origin::root(), oracle1), Error::<TestRuntime>::TooManyOracles);
+		assert_noop!(OcwDemo::remove_oracle(Origin::signed(oracle1), oracle1), BadOrigin);
+		assert_noop!(OcwDemo::remove_oracle(Origin::root(), oracle3), Error::<TestRuntime>::NotOracle);
+		assert_ok!(OcwDemo::remove_oracle(Origin::root(), oracle2));
+		assert_noop!(OcwDemo::add_oracle(Origin::root(), oracle1), Error::<TestRuntime>::AlreadyOracle);
+		assert_eq!(OcwDemo::oracles(), vec![oracle1]);
+
+		// Only oracles submit prices
+		assert_noop!(OcwDemo::submit_price_signed(Origin::signed(oracle2), price(5)), Error::<TestRuntime>::NotOracle);
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(oracle1), price(5)));
+		assert_eq!(OcwDemo::submissions(), vec![(oracle1, price(5))]);
+	});
+}
+
+#[test]
+fn validate_unsigned_accepts_only_oracle_prices() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let validate = |call| OcwDemo::validate_unsigned(TransactionSource::External, call);
+
+		// Non-oracles do not enter the pool
+		assert_eq!(validate(&signed_price(price(5), 1)), InvalidTransaction::BadSigner.into());
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+
+		let provides = validate(&signed_price(price(5), 1)).unwrap().provides;
+		// A second price of the oracle for the same block replaces the first one
+		assert_eq!(validate(&signed_price(price(6), 1)).unwrap().provides, provides);
+		System::set_block_number(2);
+		assert_ne!(validate(&signed_price(price(5), 2)).unwrap().provides, provides);
+
+		assert_eq!(validate(&signed_price(price(5), 3)), InvalidTransaction::Future.into());
+		let forged = match signed_price(price(5), 2) {
+			Call::submit_price_unsigned_with_signed_payload(payload, signature) =>
+				Call::submit_price_unsigned_with_signed_payload(PricePayload { price: price(50), ..payload }, signature),
+			_ => unreachable!(),
+		};
+		assert_eq!(validate(&forged), InvalidTransaction::BadProof.into());
+
+		// A payload is replayed once it left the pool
+		let replayed = signed_price(price(5), 2);
+		System::set_block_number(2 + UNSIGNED_TXS_LONGEVITY as u64);
+		assert!(validate(&replayed).is_ok());
+		System::set_block_number(3 + UNSIGNED_TXS_LONGEVITY as u64);
+		assert_eq!(validate(&replayed), InvalidTransaction::Stale.into());
+	});
+}
+
+#[test]
+fn submit_price_unsigned_with_signed_payload_rechecks_oracle() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		let submit = |call| match call {
+			Call::submit_price_unsigned_with_signed_payload(payload, signature) =>
+				OcwDemo::submit_price_unsigned_with_signed_payload(Origin::none(), payload, signature),
+			_ => unreachable!(),
+		};
+
+		assert_ok!(submit(signed_price(price(5), 1)));
+		assert_eq!(OcwDemo::submissions(), vec![(local_oracle(), price(5))]);
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::NewPrice(Some(local_oracle()), price(5)))));
+
+		// The oracle is removed while its price is in the pool
+		assert_ok!(OcwDemo::remove_oracle(Origin::root(), local_oracle()));
+		assert_noop!(submit(signed_price(price(6), 1)), Error::<TestRuntime>::NotOracle);
+
+		// The price gets stale while it is in the pool
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		System::set_block_number(2 + UNSIGNED_TXS_LONGEVITY as u64);
+		assert_noop!(submit(signed_price(price(6), 1)), Error::<TestRuntime>::StalePrice);
+	});
+}
+
+#[test]
+fn on_finalize_stores_median_of_round() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		let (oracle1, oracle2) = (sr25519::Public::from_raw([1; 32]), sr25519::Public::from_raw([2; 32]));
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), oracle1));
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), oracle2));
+
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(oracle2), price(7)));
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(oracle1), price(3)));
+		// The second price of an oracle in a round replaces its first one
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(oracle1), price(4)));
+		assert_eq!(OcwDemo::submissions(), vec![(oracle1, price(4)), (oracle2, price(7))]);
+		OcwDemo::on_finalize(1);
+
+		assert_eq!(OcwDemo::latest_round(), Some(RoundPrice {
+			round: 0,
+			price: cents(550),
+			block_number: 1,
+			timestamp: 6_000,
+			participants: 2,
+		}));
+		assert_eq!(OcwDemo::prices(), VecDeque::from(vec![cents(550)]));
+		assert!(OcwDemo::submissions().is_empty());
+		assert!(System::events()
+			.iter()
+			.any(|er| er.event == TestEvent::ocw_demo(RawEvent::PriceFinalized(0, cents(550), 2))));
+
+		// A block without submissions does not finalize a round
+		System::set_block_number(2);
+		OcwDemo::on_finalize(2);
+		assert_eq!(OcwDemo::latest_round().map(|latest| latest.round), Some(0));
+
+		System::set_block_number(3);
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(oracle2), price(6)));
+		OcwDemo::on_finalize(3);
+		assert_eq!(OcwDemo::latest_round(), Some(RoundPrice {
+			round: 1,
+			price: price(6),
+			block_number: 3,
+			timestamp: 18_000,
+			participants: 1,
+		}));
+		assert_eq!(OcwDemo::prices(), VecDeque::from(vec![cents(550), price(6)]));
+	});
+}
+
+#[test]
+fn price_provider_reports_latest_price_and_age() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		assert_eq!(<OcwDemo as PriceProvider<u64>>::latest_price(), None);
+		assert_eq!(<() as PriceProvider<u64>>::latest_price(), None);
+
+		assert_ok!(OcwDemo::add_oracle(Origin::root(), local_oracle()));
+		assert_ok!(OcwDemo::submit_price_signed(Origin::signed(local_oracle()), price(5)));
+		// Submitted prices are not provided before the round is finalized
+		assert_eq!(<OcwDemo as PriceProvider<u64>>::latest_price(), None);
+		OcwDemo::on_finalize(1);
+		assert_eq!(<OcwDemo as PriceProvider<u64>>::latest_price(), Some((price(5), 0)));
+
+		System::set_block_number(4);
+		assert_eq!(<OcwDemo as PriceProvider<u64>>::latest_price(), Some((price(5), 3)));
+	});
+}
+
+#[test]
+fn migration_converts_integer_prices() {
+	let (mut t, _, _) = ExternalityBuilder::build();
+	t.execute_with(|| {
+		use frame_support::{storage::unhashed, traits::OnRuntimeUpgrade, StorageValue};
+
+		StorageVersion::put(Releases::V1IntegerPrices);
+		unhashed::put(&Prices::hashed_key(), &VecDeque::from(vec![4u32, 5]));
+
+		<OcwDemo as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(OcwDemo::prices(), VecDeque::from(vec![price(4), price(5)]));
+		assert_eq!(OcwDemo::storage_version(), Releases::V2FixedPrices);
+
+		// Already migrated prices are left as they are
+		<OcwDemo as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(OcwDemo::prices(), VecDeque::from(vec![price(4), price(5)]));
+	});
+}
@@ -268,11 +268,25 @@ impl pallet_template::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const MaxFeeds: u32 = 10;
+	pub const MaxFeedLength: u32 = 512;
+	pub const MaxPriceDeviation: Permill = Permill::from_percent(5);
+	pub const MinPriceSources: u32 = 2;
+	pub const MaxOracles: u32 = 20;
+}
+
 /// For pallet-ocw-demo
 impl pallet_ocw_demo::Trait for Runtime {
 	type AuthorityId = pallet_ocw_demo::crypto::TestAuthId;
 	type Call = Call;
 	type Event = Event;
+	type MaxFeeds = MaxFeeds;
+	type MaxFeedLength = MaxFeedLength;
+	type MaxPriceDeviation = MaxPriceDeviation;
+	type MinPriceSources = MinPriceSources;
+	type MaxOracles = MaxOracles;
+	type UnixTime = Timestamp;
 }
 
 impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
@@ -342,7 +356,7 @@ construct_runtime!(
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
 		// Include the custom logic from the template pallet in the runtime.
 		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
-		OcwDemo: pallet_ocw_demo::{Module, Call, Storage, Event<T>, ValidateUnsigned},
+		OcwDemo: pallet_ocw_demo::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
 	}
 );
 
@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, ContractsConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, Balance, BalancesConfig, ContractsConfig, GenesisConfig, GrandpaConfig,
+	KittiesModuleConfig, DogsModuleConfig, SudoConfig, SystemConfig, WASM_BINARY, Signature, MILLICENTS,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -38,6 +38,19 @@ pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
 	)
 }
 
+/// Kitties of the development chain, a few of them for sale. (owner, dna, price)
+fn dev_kitties() -> Vec<(AccountId, [u8; 16], Option<Balance>)> {
+	let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+	let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+	vec![
+		(alice.clone(), [0x12, 0x03, 0x0a, 0x45, 0x07, 0x9c, 0x21, 0x3e, 0x86, 0x0b, 0x11, 0x62, 0x08, 0xd4, 0x5f, 0x70], None),
+		(alice.clone(), [0x8e, 0x05, 0x13, 0x02, 0x91, 0x4a, 0x6b, 0x0c, 0x0d, 0x88, 0x27, 0x31, 0x03, 0x5a, 0xe0, 0x19], Some(10_000 * MILLICENTS)),
+		(alice, [0x03, 0x8a, 0x04, 0x87, 0x05, 0x77, 0x3c, 0xb1, 0x09, 0x0e, 0x92, 0x06, 0x8b, 0x14, 0x2d, 0x4e], None),
+		(bob.clone(), [0x0b, 0x0f, 0x91, 0x08, 0x83, 0x26, 0x58, 0x1f, 0x84, 0x02, 0x06, 0x8e, 0x01, 0x7b, 0xc3, 0x2a], Some(25_000 * MILLICENTS)),
+		(bob, [0x86, 0x11, 0x0c, 0x05, 0x0a, 0xe7, 0x40, 0x9d, 0x07, 0x8c, 0x10, 0x03, 0x89, 0x36, 0x52, 0xfb], None),
+	]
+}
+
 pub fn development_config() -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
 
@@ -63,6 +76,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
 			true,
+			dev_kitties(),
 		),
 		// Bootnodes
 		vec![],
@@ -111,6 +125,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
 			true,
+			vec![],
 		),
 		// Bootnodes
 		vec![],
@@ -132,6 +147,7 @@ fn testnet_genesis(
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
 	enable_println: bool,
+	initial_kitties: Vec<(AccountId, [u8; 16], Option<Balance>)>,
 ) -> GenesisConfig {
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
@@ -159,5 +175,11 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		pallet_kitties: Some(KittiesModuleConfig {
+			kitties: initial_kitties,
+		}),
+		pallet_kitties_Instance1: Some(DogsModuleConfig {
+			kitties: vec![],
+		}),
 	}
 }
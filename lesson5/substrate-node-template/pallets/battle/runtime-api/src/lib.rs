@@ -0,0 +1,16 @@
+//! Runtime API definition for the battle pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+pub use pallet_battle::BattleRecord;
+
+sp_api::decl_runtime_apis! {
+	pub trait BattleApi<KittyIndex> where
+		KittyIndex: Codec,
+	{
+		/// The `count` kitties with the most wins, fewer losses first on a tie.
+		fn leaderboard(count: u32) -> Vec<(KittyIndex, BattleRecord)>;
+	}
+}
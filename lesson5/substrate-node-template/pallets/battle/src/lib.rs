@@ -0,0 +1,366 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! A battle mini-game between the kitties of pallet_kitties
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, ensure, StorageValue, StorageMap,
+	traits::{Randomness, Currency, ReservableCurrency, BalanceStatus, Get},
+	dispatch::DispatchResult, weights::Weight,
+};
+use frame_system::{self as system, ensure_signed};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{RuntimeDebug, traits::One};
+use sp_std::{prelude::*, cmp::Ordering};
+use pallet_kitties::{with_transaction_result, KittyTraits, OnKittyBurned};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Strength of every kitty before its traits, so weak kitties can still win.
+pub const BASE_STRENGTH: u64 = 100;
+
+/// Strength added by each variant index of a trait.
+pub const STRENGTH_PER_VARIANT: u64 = 10;
+
+/// Number of kitties kept in the leaderboard.
+pub const MAX_LEADERBOARD: u32 = 100;
+
+pub type ChallengeId = u64;
+
+/// A battle waiting for the user of `opponent_kitty` to accept it.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct Challenge<AccountId, KittyIndex, Balance> {
+	pub challenger: AccountId,
+	pub challenger_kitty: KittyIndex,
+	pub opponent_kitty: KittyIndex,
+	/// Reserved from both players, the winner takes the wager of the loser.
+	pub wager: Balance,
+}
+
+/// An accepted challenge waiting for the randomness of block `resolve_at`.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct PendingBattle<AccountId, KittyIndex, Balance, BlockNumber> {
+	pub challenge: Challenge<AccountId, KittyIndex, Balance>,
+	pub opponent: AccountId,
+	/// Strengths of the kitties when the challenge was accepted.
+	pub challenger_strength: u64,
+	pub opponent_strength: u64,
+	pub resolve_at: BlockNumber,
+}
+
+/// Battle results of a kitty.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq, Default)]
+pub struct BattleRecord {
+	pub wins: u32,
+	pub losses: u32,
+}
+
+/// The pallet's configuration trait.
+pub trait Trait: pallet_kitties::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Blocks between accepting a challenge and the randomness deciding the battle, at least one.
+	type BattleDelay: Get<Self::BlockNumber>;
+	/// Maximum number of battles decided in the same block.
+	type MaxBattlesPerBlock: Get<u32>;
+	/// Blocks after a battle before the same two kitties may battle again.
+	type RematchCooldown: Get<Self::BlockNumber>;
+}
+
+type BalanceOf<T> = <<T as pallet_kitties::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type ChallengeOf<T> = Challenge<<T as system::Trait>::AccountId, <T as pallet_kitties::Trait>::KittyIndex, BalanceOf<T>>;
+type PendingBattleOf<T> = PendingBattle<
+	<T as system::Trait>::AccountId, <T as pallet_kitties::Trait>::KittyIndex, BalanceOf<T>, <T as system::Trait>::BlockNumber,
+>;
+type Kitties<T> = pallet_kitties::Module<T>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Battle {
+		/// Id of the next challenge.
+		pub NextChallengeId get(fn next_challenge_id): ChallengeId;
+
+		/// Challenges waiting to be accepted.
+		pub Challenges get(fn challenges): map hasher(twox_64_concat) ChallengeId => Option<ChallengeOf<T>>;
+
+		/// Accepted challenges waiting for their battle.
+		pub PendingBattles get(fn pending_battle): map hasher(twox_64_concat) ChallengeId => Option<PendingBattleOf<T>>;
+
+		/// Battles decided at each block, at most `MaxBattlesPerBlock` per block.
+		pub BattleQueue get(fn battle_queue): map hasher(twox_64_concat) T::BlockNumber => Vec<ChallengeId>;
+
+		/// Wins and losses of each kitty.
+		pub KittyRecords get(fn kitty_record): map hasher(blake2_128_concat) T::KittyIndex => BattleRecord;
+
+		/// The `MAX_LEADERBOARD` best kitties, most wins first and fewer losses first on a tie.
+		/// Kitties are ranked after each battle, one falling out comes back by winning again.
+		pub Leaderboard get(fn top_kitties): Vec<(T::KittyIndex, BattleRecord)>;
+
+		/// Kitty pairs which battled recently, lowest id first, and the block they may battle again.
+		pub Rematches get(fn rematch_at): map hasher(blake2_128_concat) (T::KittyIndex, T::KittyIndex) =>
+			Option<T::BlockNumber>;
+
+		/// Kitty pairs allowed to battle again at each block.
+		pub RematchExpiries get(fn rematch_expiries): map hasher(twox_64_concat) T::BlockNumber =>
+			Vec<(T::KittyIndex, T::KittyIndex)>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as pallet_kitties::Trait>::KittyIndex,
+		<T as system::Trait>::BlockNumber,
+		Balance = BalanceOf<T>,
+	{
+		/// A kitty is challenged. (challenger, challenge_id, challenger_kitty, opponent_kitty, wager)
+		Challenged(AccountId, ChallengeId, KittyIndex, KittyIndex, Balance),
+		/// A challenge is cancelled. (challenger, challenge_id)
+		ChallengeCancelled(AccountId, ChallengeId),
+		/// A challenge is accepted, the battle is decided at a later block. (opponent, challenge_id, resolve_at)
+		ChallengeAccepted(AccountId, ChallengeId, BlockNumber),
+		/// A battle is fought. (challenge_id, winner, winner_kitty, loser_kitty, wager)
+		Battled(ChallengeId, AccountId, KittyIndex, KittyIndex, Balance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		InvalidKittyId,
+		RequireOwner,
+		RequireDifferentOwner,
+		InvalidChallengeId,
+		RequireChallenger,
+		/// The challenger can not use its kitty anymore, only cancelling is possible.
+		ChallengerKittyUnavailable,
+		/// Too many battles are decided in the same block already.
+		TooManyBattles,
+		/// The two kitties battled each other too recently.
+		RematchTooSoon,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let due = BattleQueue::<T>::take(now);
+			for challenge_id in due.iter() {
+				if let Some(battle) = PendingBattles::<T>::take(challenge_id) {
+					Self::do_battle(*challenge_id, battle);
+				}
+			}
+			let expired = RematchExpiries::<T>::take(now);
+			for pair in expired.iter() {
+				Rematches::<T>::remove(pair);
+			}
+
+			T::DbWeight::get().reads_writes(
+				2 + 10 * due.len() as Weight,
+				2 + 9 * due.len() as Weight + expired.len() as Weight,
+			)
+		}
+
+		/// Challenge the kitty of another account, reserving `wager` if any
+		#[weight = 0]
+		pub fn challenge(
+			origin,
+			my_kitty: T::KittyIndex,
+			their_kitty: T::KittyIndex,
+			wager: Option<BalanceOf<T>>,
+		) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				ensure!(<Kitties<T>>::can_use(&sender, my_kitty), Error::<T>::RequireOwner);
+				ensure!(<Kitties<T>>::kitties(their_kitty).is_some(), Error::<T>::InvalidKittyId);
+				// Battling your own kitties would farm the leaderboard
+				ensure!(!<Kitties<T>>::can_use(&sender, their_kitty), Error::<T>::RequireDifferentOwner);
+				// Two accounts battling the same kitties over and over would farm the leaderboard too
+				ensure!(!Rematches::<T>::contains_key(Self::pair(my_kitty, their_kitty)), Error::<T>::RematchTooSoon);
+
+				let wager = wager.unwrap_or_default();
+				T::Currency::reserve(&sender, wager)?;
+
+				let challenge_id = Self::next_challenge_id();
+				NextChallengeId::put(challenge_id + 1);
+				Challenges::<T>::insert(challenge_id, Challenge {
+					challenger: sender.clone(),
+					challenger_kitty: my_kitty,
+					opponent_kitty: their_kitty,
+					wager,
+				});
+
+				Self::deposit_event(RawEvent::Challenged(sender, challenge_id, my_kitty, their_kitty, wager));
+				Ok(())
+			})?;
+		}
+
+		/// Cancel a challenge not accepted yet, releasing the wager
+		#[weight = 0]
+		pub fn cancel_challenge(origin, challenge_id: ChallengeId) {
+			let sender = ensure_signed(origin)?;
+
+			let challenge = Self::challenges(challenge_id).ok_or(Error::<T>::InvalidChallengeId)?;
+			ensure!(challenge.challenger == sender, Error::<T>::RequireChallenger);
+
+			Challenges::<T>::remove(challenge_id);
+			T::Currency::unreserve(&sender, challenge.wager);
+
+			Self::deposit_event(RawEvent::ChallengeCancelled(sender, challenge_id));
+		}
+
+		/// Accept a challenge against your kitty, matching the wager
+		/// The battle is decided by the randomness of a later block
+		#[weight = 0]
+		pub fn accept_challenge(origin, challenge_id: ChallengeId) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let challenge = Self::challenges(challenge_id).ok_or(Error::<T>::InvalidChallengeId)?;
+				ensure!(<Kitties<T>>::can_use(&sender, challenge.opponent_kitty), Error::<T>::RequireOwner);
+				ensure!(
+					<Kitties<T>>::can_use(&challenge.challenger, challenge.challenger_kitty),
+					Error::<T>::ChallengerKittyUnavailable
+				);
+				let pair = Self::pair(challenge.challenger_kitty, challenge.opponent_kitty);
+				ensure!(!Rematches::<T>::contains_key(pair), Error::<T>::RematchTooSoon);
+				let challenger_strength = Self::strength(challenge.challenger_kitty).ok_or(Error::<T>::InvalidKittyId)?;
+				let opponent_strength = Self::strength(challenge.opponent_kitty).ok_or(Error::<T>::InvalidKittyId)?;
+
+				T::Currency::reserve(&sender, challenge.wager)?;
+				Challenges::<T>::remove(challenge_id);
+
+				// Nobody knows the randomness deciding the battle when accepting it
+				let resolve_at = <frame_system::Module<T>>::block_number() + T::BattleDelay::get().max(One::one());
+				BattleQueue::<T>::try_mutate(resolve_at, |queue| -> DispatchResult {
+					ensure!((queue.len() as u32) < T::MaxBattlesPerBlock::get(), Error::<T>::TooManyBattles);
+					queue.push(challenge_id);
+					Ok(())
+				})?;
+				// Battles per block are bounded, so are the pairs allowed again at each block
+				let rematch_at = resolve_at + T::RematchCooldown::get();
+				Rematches::<T>::insert(pair, rematch_at);
+				RematchExpiries::<T>::append(rematch_at, pair);
+				PendingBattles::<T>::insert(challenge_id, PendingBattle {
+					challenge,
+					opponent: sender.clone(),
+					challenger_strength,
+					opponent_strength,
+					resolve_at,
+				});
+
+				Self::deposit_event(RawEvent::ChallengeAccepted(sender, challenge_id, resolve_at));
+				Ok(())
+			})?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Strength of a kitty in battle, from the traits of its DNA.
+	pub fn strength(kitty_id: T::KittyIndex) -> Option<u64> {
+		let kitty = <Kitties<T>>::kitties(kitty_id)?;
+		let traits = KittyTraits::decode_dna(&kitty.0, &<T as pallet_kitties::Trait>::Genes::get());
+		let variants: u64 = traits.variants().iter().map(|variant| *variant as u64).sum();
+		Some(BASE_STRENGTH + variants * STRENGTH_PER_VARIANT)
+	}
+
+	/// Two kitties ordered by id, the key of their rematch cooldown.
+	fn pair(kitty_1: T::KittyIndex, kitty_2: T::KittyIndex) -> (T::KittyIndex, T::KittyIndex) {
+		(kitty_1.min(kitty_2), kitty_1.max(kitty_2))
+	}
+
+	/// Whether the challenger wins, with a chance proportional to its share of the total strength.
+	pub fn challenger_wins(challenger_strength: u64, opponent_strength: u64, seed: &[u8; 32]) -> bool {
+		let mut roll = [0u8; 8];
+		roll.copy_from_slice(&seed[..8]);
+		let total = challenger_strength.saturating_add(opponent_strength).max(1);
+		u64::from_le_bytes(roll) % total < challenger_strength
+	}
+
+	/// The `count` kitties with the most wins, at most `MAX_LEADERBOARD`.
+	pub fn leaderboard(count: u32) -> Vec<(T::KittyIndex, BattleRecord)> {
+		let mut records = Self::top_kitties();
+		records.truncate(count.min(MAX_LEADERBOARD) as usize);
+		records
+	}
+
+	/// Most wins first, then fewer losses, then lower kitty id.
+	fn rank(
+		(id1, r1): &(T::KittyIndex, BattleRecord),
+		(id2, r2): &(T::KittyIndex, BattleRecord),
+	) -> Ordering {
+		r2.wins.cmp(&r1.wins).then(r1.losses.cmp(&r2.losses)).then(id1.cmp(id2))
+	}
+
+	/// Move a kitty to its rank in the leaderboard, dropping the last one when full.
+	fn update_leaderboard(kitty_id: T::KittyIndex, record: BattleRecord) {
+		Leaderboard::<T>::mutate(|board| {
+			board.retain(|(id, _)| *id != kitty_id);
+			let entry = (kitty_id, record);
+			let position = board.iter()
+				.position(|other| Self::rank(&entry, other) == Ordering::Less)
+				.unwrap_or(board.len());
+			if position < MAX_LEADERBOARD as usize {
+				board.insert(position, entry);
+				board.truncate(MAX_LEADERBOARD as usize);
+			}
+		});
+	}
+
+	/// Update the record of a kitty, unless it was burned before the battle.
+	fn record_result(kitty_id: T::KittyIndex, f: impl FnOnce(&mut BattleRecord)) {
+		if <Kitties<T>>::kitties(kitty_id).is_none() {
+			return;
+		}
+		let record = KittyRecords::<T>::mutate(kitty_id, |record| {
+			f(record);
+			record.clone()
+		});
+		Self::update_leaderboard(kitty_id, record);
+	}
+
+	/// Decide an accepted battle with the randomness of the current block.
+	fn do_battle(challenge_id: ChallengeId, battle: PendingBattleOf<T>) {
+		let challenge = battle.challenge;
+		let seed = (
+			<T as pallet_kitties::Trait>::Randomness::random(b"kitty battle"),
+			challenge_id,
+		).using_encoded(blake2_256);
+
+		let (winner, loser, winner_kitty, loser_kitty) =
+			if Self::challenger_wins(battle.challenger_strength, battle.opponent_strength, &seed) {
+				(challenge.challenger, battle.opponent, challenge.challenger_kitty, challenge.opponent_kitty)
+			} else {
+				(battle.opponent, challenge.challenger, challenge.opponent_kitty, challenge.challenger_kitty)
+			};
+
+		Self::settle_wager(&winner, &loser, challenge.wager);
+
+		Self::record_result(winner_kitty, |record| record.wins = record.wins.saturating_add(1));
+		Self::record_result(loser_kitty, |record| record.losses = record.losses.saturating_add(1));
+
+		Self::deposit_event(RawEvent::Battled(challenge_id, winner, winner_kitty, loser_kitty, challenge.wager));
+	}
+
+	/// The winner gets its wager back and the wager of the loser.
+	fn settle_wager(winner: &T::AccountId, loser: &T::AccountId, wager: BalanceOf<T>) {
+		T::Currency::unreserve(winner, wager);
+		// The winner exists with its wager reserved, repatriating to it does not fail
+		let _ = T::Currency::repatriate_reserved(loser, winner, wager, BalanceStatus::Free);
+	}
+}
+
+impl<T: Trait> OnKittyBurned<T::KittyIndex> for Module<T> {
+	fn on_kitty_burned(kitty_id: T::KittyIndex) {
+		KittyRecords::<T>::remove(kitty_id);
+		Leaderboard::<T>::mutate(|board| board.retain(|(id, _)| *id != kitty_id));
+	}
+}
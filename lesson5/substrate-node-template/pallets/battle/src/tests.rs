@@ -0,0 +1,241 @@
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_noop, traits::OnInitialize};
+use frame_system as system;
+use super::*;
+
+// Account 1 owns kitty 0, account 2 owns kitty 1 and account 3 owns kitty 2
+fn create_kitties() {
+	for owner in 1..=3 {
+		assert_ok!(KittiesModule::create(Origin::signed(owner)));
+	}
+}
+
+#[test]
+fn challenge_reserves_wager() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, Some(50)));
+		assert_eq!(BattleModule::challenges(0), Some(Challenge {
+			challenger: 1,
+			challenger_kitty: 0,
+			opponent_kitty: 1,
+			wager: 50,
+		}));
+		assert_eq!(Balances::reserved_balance(1), 100 + 50);
+		assert_eq!(BattleModule::next_challenge_id(), 1);
+	})
+}
+
+#[test]
+fn challenge_failed_when_not_owner_or_same_owner() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+		assert_noop!(BattleModule::challenge(Origin::signed(1), 1, 2, None), Error::<Test>::RequireOwner);
+		assert_noop!(BattleModule::challenge(Origin::signed(1), 0, 9, None), Error::<Test>::InvalidKittyId);
+		assert_noop!(BattleModule::challenge(Origin::signed(1), 0, 3, None), Error::<Test>::RequireDifferentOwner);
+	})
+}
+
+#[test]
+fn cancel_challenge_releases_wager() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, Some(50)));
+
+		assert_noop!(BattleModule::cancel_challenge(Origin::signed(2), 0), Error::<Test>::RequireChallenger);
+		assert_ok!(BattleModule::cancel_challenge(Origin::signed(1), 0));
+
+		assert_eq!(BattleModule::challenges(0), None);
+		assert_eq!(Balances::reserved_balance(1), 100);
+		assert_noop!(BattleModule::accept_challenge(Origin::signed(2), 0), Error::<Test>::InvalidChallengeId);
+	})
+}
+
+// Decide the battles accepted at block 1
+fn resolve_battles() {
+	system::Module::<Test>::set_block_number(3);
+	BattleModule::on_initialize(3);
+}
+
+#[test]
+fn accept_challenge_waits_for_later_block() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, Some(50)));
+
+		assert_noop!(BattleModule::accept_challenge(Origin::signed(3), 0), Error::<Test>::RequireOwner);
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(2), 0));
+
+		assert_eq!(BattleModule::challenges(0), None);
+		assert_eq!(BattleModule::battle_queue(3), vec![0]);
+		assert_eq!(BattleModule::pending_battle(0).map(|battle| battle.resolve_at), Some(3));
+		assert_eq!(BattleModule::kitty_record(0), BattleRecord::default());
+		assert_eq!(Balances::reserved_balance(2), 100 + 50);
+	})
+}
+
+#[test]
+fn accept_challenge_failed_when_too_many_battles() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, None));
+		assert_ok!(BattleModule::challenge(Origin::signed(3), 2, 1, None));
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(2), 0));
+
+		assert_noop!(BattleModule::accept_challenge(Origin::signed(2), 1), Error::<Test>::TooManyBattles);
+	})
+}
+
+#[test]
+fn battle_pays_winner_and_records_result() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, Some(50)));
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(2), 0));
+		resolve_battles();
+
+		assert_eq!(BattleModule::pending_battle(0), None);
+		assert_eq!(BattleModule::battle_queue(3), Vec::<ChallengeId>::new());
+		let (winner, loser, winner_kitty, loser_kitty) = if BattleModule::kitty_record(0).wins == 1 {
+			(1, 2, 0, 1)
+		} else {
+			(2, 1, 1, 0)
+		};
+		assert_eq!(BattleModule::kitty_record(winner_kitty), BattleRecord { wins: 1, losses: 0 });
+		assert_eq!(BattleModule::kitty_record(loser_kitty), BattleRecord { wins: 0, losses: 1 });
+		assert_eq!(BattleModule::leaderboard(10), vec![
+			(winner_kitty, BattleRecord { wins: 1, losses: 0 }),
+			(loser_kitty, BattleRecord { wins: 0, losses: 1 }),
+		]);
+
+		// Only the kitty deposits stay reserved
+		assert_eq!(Balances::reserved_balance(winner), 100);
+		assert_eq!(Balances::reserved_balance(loser), 100);
+		assert_eq!(Balances::free_balance(winner), 1000 - 100 + 50);
+		assert_eq!(Balances::free_balance(loser), 1000 - 100 - 50);
+	})
+}
+
+#[test]
+fn rematch_waits_for_cooldown() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, None));
+		assert_ok!(BattleModule::challenge(Origin::signed(2), 1, 0, None));
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(2), 0));
+		assert_eq!(BattleModule::rematch_at((0, 1)), Some(3 + 5));
+
+		// Either kitty may challenge the other again only after the cooldown
+		assert_noop!(BattleModule::accept_challenge(Origin::signed(1), 1), Error::<Test>::RematchTooSoon);
+		resolve_battles();
+		assert_noop!(BattleModule::challenge(Origin::signed(2), 1, 0, None), Error::<Test>::RematchTooSoon);
+		assert_ok!(BattleModule::challenge(Origin::signed(3), 2, 0, None));
+
+		system::Module::<Test>::set_block_number(8);
+		BattleModule::on_initialize(8);
+		assert_eq!(BattleModule::rematch_at((0, 1)), None);
+		assert_eq!(BattleModule::rematch_expiries(8), Vec::<(u32, u32)>::new());
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(1), 1));
+	})
+}
+
+#[test]
+fn burned_kitties_lose_their_records() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, Some(50)));
+		assert_ok!(BattleModule::accept_challenge(Origin::signed(2), 0));
+
+		// Kitty 0 is burned before the battle and gets no record, the wagers are still settled
+		assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+		resolve_battles();
+		assert_eq!(BattleModule::kitty_record(0), BattleRecord::default());
+		assert_eq!(Balances::reserved_balance(1) + Balances::reserved_balance(2), 100);
+		assert_eq!(BattleModule::leaderboard(10).len(), 1);
+
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 1));
+		assert!(!KittyRecords::<Test>::contains_key(1));
+		assert_eq!(BattleModule::leaderboard(10), vec![]);
+	})
+}
+
+#[test]
+fn accept_challenge_failed_when_challenger_kitty_gone() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		assert_ok!(BattleModule::challenge(Origin::signed(1), 0, 1, None));
+		assert_ok!(KittiesModule::transfer(Origin::signed(1), 3, 0));
+
+		assert_noop!(
+			BattleModule::accept_challenge(Origin::signed(2), 0),
+			Error::<Test>::ChallengerKittyUnavailable
+		);
+	})
+}
+
+#[test]
+fn challenger_wins_in_proportion_to_strength() {
+	let seed = |roll: u64| {
+		let mut seed = [0u8; 32];
+		seed[..8].copy_from_slice(&roll.to_le_bytes());
+		seed
+	};
+
+	assert!(BattleModule::challenger_wins(100, 300, &seed(99)));
+	assert!(!BattleModule::challenger_wins(100, 300, &seed(100)));
+	assert!(!BattleModule::challenger_wins(100, 300, &seed(399)));
+	assert!(BattleModule::challenger_wins(100, 300, &seed(400)));
+	assert!(!BattleModule::challenger_wins(0, 0, &seed(7)));
+}
+
+#[test]
+fn strength_comes_from_dna_traits() {
+	new_test_ext().execute_with(|| {
+		create_kitties();
+		let kitty = KittiesModule::kitties(0).unwrap();
+		let traits = KittyTraits::decode_dna(&kitty.0, &pallet_kitties::dna::GENES);
+		let variants: u64 = traits.variants().iter().map(|variant| *variant as u64).sum();
+
+		assert_eq!(BattleModule::strength(0), Some(BASE_STRENGTH + variants * STRENGTH_PER_VARIANT));
+		// Burning other kitties does not change it
+		assert_ok!(KittiesModule::burn(Origin::signed(2), 1));
+		assert_eq!(BattleModule::strength(0), Some(BASE_STRENGTH + variants * STRENGTH_PER_VARIANT));
+		assert_eq!(BattleModule::strength(1), None);
+	})
+}
+
+#[test]
+fn leaderboard_sorts_by_wins_then_losses() {
+	new_test_ext().execute_with(|| {
+		BattleModule::update_leaderboard(0, BattleRecord { wins: 1, losses: 3 });
+		BattleModule::update_leaderboard(1, BattleRecord { wins: 2, losses: 0 });
+		BattleModule::update_leaderboard(2, BattleRecord { wins: 1, losses: 1 });
+		BattleModule::update_leaderboard(3, BattleRecord { wins: 0, losses: 2 });
+		BattleModule::update_leaderboard(1, BattleRecord { wins: 2, losses: 2 });
+
+		assert_eq!(BattleModule::leaderboard(3), vec![
+			(1, BattleRecord { wins: 2, losses: 2 }),
+			(2, BattleRecord { wins: 1, losses: 1 }),
+			(0, BattleRecord { wins: 1, losses: 3 }),
+		]);
+	})
+}
+
+#[test]
+fn leaderboard_keeps_best_kitties() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..MAX_LEADERBOARD {
+			BattleModule::update_leaderboard(kitty_id, BattleRecord { wins: 2, losses: 0 });
+		}
+		BattleModule::update_leaderboard(MAX_LEADERBOARD, BattleRecord { wins: 1, losses: 0 });
+		BattleModule::update_leaderboard(MAX_LEADERBOARD + 1, BattleRecord { wins: 3, losses: 0 });
+
+		let board = BattleModule::top_kitties();
+		assert_eq!(board.len(), MAX_LEADERBOARD as usize);
+		assert_eq!(board[0], (MAX_LEADERBOARD + 1, BattleRecord { wins: 3, losses: 0 }));
+		assert_eq!(board[board.len() - 1], (MAX_LEADERBOARD - 2, BattleRecord { wins: 2, losses: 0 }));
+	})
+}
@@ -0,0 +1,362 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Fractional ownership of the kitties of pallet_kitties
+//!
+//! A fractionalized kitty is held by a vault account of the pallet, so it can not be
+//! transferred, listed, leased or bred until it is redeemed or bought out.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, ensure, StorageMap, StorageDoubleMap,
+	traits::{Currency, ReservableCurrency, ExistenceRequirement, Get},
+	dispatch::DispatchResult,
+};
+use frame_system::{self as system, ensure_signed};
+use sp_runtime::{ModuleId, Perbill, RuntimeDebug, traits::{AccountIdConversion, Zero}};
+use sp_std::prelude::*;
+use pallet_kitties::with_transaction_result;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub type Shares = u64;
+
+/// A kitty locked against fungible shares.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct Vault<AccountId, Balance> {
+	/// Account that fractionalized the kitty.
+	pub owner: AccountId,
+	pub total_shares: Shares,
+	/// Lowest price of a buyout.
+	pub reserve_price: Balance,
+}
+
+/// An offer to buy a fractionalized kitty, the price is reserved from the buyer.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct Buyout<AccountId, Balance> {
+	pub buyer: AccountId,
+	pub price: Balance,
+	/// Shares of the holders approving the buyout.
+	pub approvals: Shares,
+}
+
+/// Price of a bought out kitty not claimed yet by the share holders.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct Proceeds<Balance> {
+	pub remaining: Balance,
+	pub remaining_shares: Shares,
+}
+
+/// The pallet's configuration trait.
+pub trait Trait: pallet_kitties::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Id of the pallet, the vault account of each kitty is derived from it.
+	type ModuleId: Get<ModuleId>;
+}
+
+type BalanceOf<T> = <<T as pallet_kitties::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type VaultOf<T> = Vault<<T as system::Trait>::AccountId, BalanceOf<T>>;
+type BuyoutOf<T> = Buyout<<T as system::Trait>::AccountId, BalanceOf<T>>;
+type Kitties<T> = pallet_kitties::Module<T>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Fractional {
+		/// Fractionalized kitties, kept after a buyout until all the proceeds are claimed.
+		pub Vaults get(fn vault): map hasher(blake2_128_concat) T::KittyIndex => Option<VaultOf<T>>;
+
+		/// Shares of each holder. (kitty_id, holder) => shares
+		pub ShareBalances get(fn shares): double_map hasher(blake2_128_concat) T::KittyIndex,
+			hasher(blake2_128_concat) T::AccountId => Shares;
+
+		/// Pending buyout of a kitty, a higher price replaces it.
+		pub Buyouts get(fn buyout): map hasher(blake2_128_concat) T::KittyIndex => Option<BuyoutOf<T>>;
+
+		/// Shares approving the pending buyout of a kitty. (kitty_id, holder) => shares
+		/// Lowered when the holder transfers shares, so every share approves at most once.
+		pub BuyoutVotes get(fn buyout_vote): double_map hasher(blake2_128_concat) T::KittyIndex,
+			hasher(blake2_128_concat) T::AccountId => Shares;
+
+		/// Price of the bought out kitties left to the share holders.
+		pub BoughtOut get(fn proceeds): map hasher(blake2_128_concat) T::KittyIndex => Option<Proceeds<BalanceOf<T>>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as system::Trait>::AccountId,
+		<T as pallet_kitties::Trait>::KittyIndex,
+		Balance = BalanceOf<T>,
+	{
+		/// A kitty is locked against shares. (owner, kitty_id, shares, reserve_price)
+		Fractionalized(AccountId, KittyIndex, Shares, Balance),
+		/// Shares are transferred. (from, to, kitty_id, shares)
+		SharesTransferred(AccountId, AccountId, KittyIndex, Shares),
+		/// A buyout is proposed. (buyer, kitty_id, price)
+		BuyoutProposed(AccountId, KittyIndex, Balance),
+		/// A buyout is cancelled or replaced by a higher one. (buyer, kitty_id)
+		BuyoutCancelled(AccountId, KittyIndex),
+		/// A holder approves the buyout with its shares. (holder, kitty_id, shares)
+		BuyoutApproved(AccountId, KittyIndex, Shares),
+		/// A kitty is bought out, the holders claim the price less the breeder royalty.
+		/// (buyer, kitty_id, price, royalty)
+		BoughtOut(AccountId, KittyIndex, Balance, Balance),
+		/// A kitty is redeemed by the holder of all its shares. (holder, kitty_id)
+		Redeemed(AccountId, KittyIndex),
+		/// A holder claims its part of a buyout. (holder, kitty_id, shares, amount)
+		ProceedsClaimed(AccountId, KittyIndex, Shares, Balance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		ZeroShares,
+		NotFractionalized,
+		InsufficientShares,
+		PriceBelowReserve,
+		/// A pending buyout is only replaced by a higher price.
+		RequireHigherPrice,
+		NoBuyout,
+		RequireBuyer,
+		RequireAllShares,
+		AlreadyBoughtOut,
+		NotBoughtOut,
+		/// The buyer does not have the price of the buyout reserved anymore.
+		PriceNotReserved,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		const ModuleId: ModuleId = T::ModuleId::get();
+
+		fn deposit_event() = default;
+
+		/// Lock a kitty in its vault and mint `shares` to the owner
+		/// The kitty can be bought out for `reserve_price` or more.
+		#[weight = 0]
+		pub fn fractionalize(origin, kitty_id: T::KittyIndex, shares: Shares, reserve_price: BalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				ensure!(!shares.is_zero(), Error::<T>::ZeroShares);
+
+				<Kitties<T>>::take_custody(&sender, &Self::vault_account(kitty_id), kitty_id)?;
+
+				Vaults::<T>::insert(kitty_id, Vault {
+					owner: sender.clone(),
+					total_shares: shares,
+					reserve_price,
+				});
+				ShareBalances::<T>::insert(kitty_id, &sender, shares);
+
+				Self::deposit_event(RawEvent::Fractionalized(sender, kitty_id, shares, reserve_price));
+				Ok(())
+			})?;
+		}
+
+		/// Transfer shares of a kitty
+		#[weight = 0]
+		pub fn transfer_shares(origin, kitty_id: T::KittyIndex, to: T::AccountId, amount: Shares) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Vaults::<T>::contains_key(kitty_id), Error::<T>::NotFractionalized);
+			let balance = Self::shares(kitty_id, &sender);
+			ensure!(balance >= amount, Error::<T>::InsufficientShares);
+
+			if sender != to {
+				let remaining = balance - amount;
+				if remaining.is_zero() {
+					ShareBalances::<T>::remove(kitty_id, &sender);
+				} else {
+					ShareBalances::<T>::insert(kitty_id, &sender, remaining);
+				}
+				ShareBalances::<T>::mutate(kitty_id, &to, |shares| *shares += amount);
+				Self::lower_vote(kitty_id, &sender, remaining);
+			}
+
+			Self::deposit_event(RawEvent::SharesTransferred(sender, to, kitty_id, amount));
+		}
+
+		/// Offer to buy a kitty for `price`, reserved until the buyout is approved or cancelled
+		#[weight = 0]
+		pub fn propose_buyout(origin, kitty_id: T::KittyIndex, price: BalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let vault = Self::vault(kitty_id).ok_or(Error::<T>::NotFractionalized)?;
+				ensure!(!BoughtOut::<T>::contains_key(kitty_id), Error::<T>::AlreadyBoughtOut);
+				ensure!(price >= vault.reserve_price, Error::<T>::PriceBelowReserve);
+
+				if let Some(buyout) = Self::buyout(kitty_id) {
+					ensure!(price > buyout.price, Error::<T>::RequireHigherPrice);
+					Self::remove_buyout(kitty_id, &buyout);
+				}
+
+				T::Currency::reserve(&sender, price)?;
+				Buyouts::<T>::insert(kitty_id, Buyout {
+					buyer: sender.clone(),
+					price,
+					approvals: 0,
+				});
+
+				Self::deposit_event(RawEvent::BuyoutProposed(sender, kitty_id, price));
+				Ok(())
+			})?;
+		}
+
+		/// Cancel a buyout not approved yet, releasing the price
+		#[weight = 0]
+		pub fn cancel_buyout(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			let buyout = Self::buyout(kitty_id).ok_or(Error::<T>::NoBuyout)?;
+			ensure!(buyout.buyer == sender, Error::<T>::RequireBuyer);
+
+			Self::remove_buyout(kitty_id, &buyout);
+		}
+
+		/// Approve the pending buyout with all your shares
+		/// The kitty goes to the buyer once more than half of the shares approve.
+		#[weight = 0]
+		pub fn approve_buyout(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let vault = Self::vault(kitty_id).ok_or(Error::<T>::NotFractionalized)?;
+				let mut buyout = Self::buyout(kitty_id).ok_or(Error::<T>::NoBuyout)?;
+				let shares = Self::shares(kitty_id, &sender);
+				ensure!(!shares.is_zero(), Error::<T>::InsufficientShares);
+
+				let previous = BuyoutVotes::<T>::get(kitty_id, &sender);
+				buyout.approvals = buyout.approvals - previous + shares;
+				BuyoutVotes::<T>::insert(kitty_id, &sender, shares);
+
+				Self::deposit_event(RawEvent::BuyoutApproved(sender, kitty_id, shares));
+
+				if buyout.approvals.saturating_mul(2) > vault.total_shares {
+					Self::do_buyout(kitty_id, &vault, buyout)?;
+				} else {
+					Buyouts::<T>::insert(kitty_id, buyout);
+				}
+				Ok(())
+			})?;
+		}
+
+		/// Take the kitty back by burning all of its shares
+		#[weight = 0]
+		pub fn redeem(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let vault = Self::vault(kitty_id).ok_or(Error::<T>::NotFractionalized)?;
+				ensure!(!BoughtOut::<T>::contains_key(kitty_id), Error::<T>::AlreadyBoughtOut);
+				ensure!(Self::shares(kitty_id, &sender) == vault.total_shares, Error::<T>::RequireAllShares);
+
+				if let Some(buyout) = Self::buyout(kitty_id) {
+					Self::remove_buyout(kitty_id, &buyout);
+				}
+
+				<Kitties<T>>::transfer_owned(&Self::vault_account(kitty_id), &sender, kitty_id)?;
+
+				ShareBalances::<T>::remove(kitty_id, &sender);
+				Vaults::<T>::remove(kitty_id);
+
+				Self::deposit_event(RawEvent::Redeemed(sender, kitty_id));
+				Ok(())
+			})?;
+		}
+
+		/// Burn your shares of a bought out kitty for their part of the price
+		#[weight = 0]
+		pub fn claim_proceeds(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let mut proceeds = Self::proceeds(kitty_id).ok_or(Error::<T>::NotBoughtOut)?;
+				let shares = ShareBalances::<T>::take(kitty_id, &sender);
+				ensure!(!shares.is_zero(), Error::<T>::InsufficientShares);
+
+				// The last holder gets the rounding leftovers
+				let amount = if shares == proceeds.remaining_shares {
+					proceeds.remaining
+				} else {
+					Perbill::from_rational_approximation(shares, proceeds.remaining_shares) * proceeds.remaining
+				};
+
+				T::Currency::transfer(&Self::vault_account(kitty_id), &sender, amount, ExistenceRequirement::AllowDeath)?;
+
+				proceeds.remaining -= amount;
+				proceeds.remaining_shares -= shares;
+				if proceeds.remaining_shares.is_zero() {
+					BoughtOut::<T>::remove(kitty_id);
+					Vaults::<T>::remove(kitty_id);
+				} else {
+					BoughtOut::<T>::insert(kitty_id, proceeds);
+				}
+
+				Self::deposit_event(RawEvent::ProceedsClaimed(sender, kitty_id, shares, amount));
+				Ok(())
+			})?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account holding a fractionalized kitty and the price of its buyout.
+	pub fn vault_account(kitty_id: T::KittyIndex) -> T::AccountId {
+		T::ModuleId::get().into_sub_account(kitty_id)
+	}
+
+	/// Keep the approval of a holder within its shares.
+	fn lower_vote(kitty_id: T::KittyIndex, holder: &T::AccountId, shares: Shares) {
+		let vote = BuyoutVotes::<T>::get(kitty_id, holder);
+		if vote <= shares {
+			return;
+		}
+		BuyoutVotes::<T>::insert(kitty_id, holder, shares);
+		Buyouts::<T>::mutate(kitty_id, |buyout| {
+			if let Some(buyout) = buyout {
+				buyout.approvals -= vote - shares;
+			}
+		});
+	}
+
+	/// Release the price of a buyout and forget its approvals.
+	fn remove_buyout(kitty_id: T::KittyIndex, buyout: &BuyoutOf<T>) {
+		T::Currency::unreserve(&buyout.buyer, buyout.price);
+		Buyouts::<T>::remove(kitty_id);
+		BuyoutVotes::<T>::remove_prefix(kitty_id);
+
+		Self::deposit_event(RawEvent::BuyoutCancelled(buyout.buyer.clone(), kitty_id));
+	}
+
+	/// Give the kitty to the buyer and keep its price in the vault for the holders.
+	/// The price is paid like a sale, so the breeder gets its royalty.
+	fn do_buyout(kitty_id: T::KittyIndex, vault: &VaultOf<T>, buyout: BuyoutOf<T>) -> DispatchResult {
+		let vault_account = Self::vault_account(kitty_id);
+
+		// Reserved funds can not be repatriated to the vault account, which does not exist
+		// for a kitty without deposit, the price is transferred instead
+		let missing = T::Currency::unreserve(&buyout.buyer, buyout.price);
+		ensure!(missing.is_zero(), Error::<T>::PriceNotReserved);
+		let royalty = <Kitties<T>>::pay_sale(&buyout.buyer, &vault_account, kitty_id, buyout.price)?;
+		<Kitties<T>>::transfer_owned(&vault_account, &buyout.buyer, kitty_id)?;
+
+		Buyouts::<T>::remove(kitty_id);
+		BuyoutVotes::<T>::remove_prefix(kitty_id);
+		BoughtOut::<T>::insert(kitty_id, Proceeds {
+			remaining: buyout.price - royalty,
+			remaining_shares: vault.total_shares,
+		});
+
+		Self::deposit_event(RawEvent::BoughtOut(buyout.buyer, kitty_id, buyout.price, royalty));
+
+		Ok(())
+	}
+}
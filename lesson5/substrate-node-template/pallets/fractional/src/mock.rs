@@ -0,0 +1,130 @@
+use crate::{Module, Trait};
+use sp_core::H256;
+use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header, ModuleId, Perbill, Permill,
+};
+use frame_system as system;
+use pallet_balances as balances;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+// Configure a mock runtime to test the pallet.
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+
+	pub const ExistentialDeposit: u64 = 1;
+	pub const KittyDeposit: u64 = 100;
+	pub const MaxAskBookWalk: u32 = 10;
+	pub const MaxLeaseExpiries: u32 = 10;
+	pub const BreederRoyalty: Permill = Permill::from_percent(10);
+	pub const MaxKittiesPerAccount: u32 = 10;
+	pub const MaxMintsPerBlock: u32 = 10;
+	pub const MetadataDepositPerByte: u64 = 1;
+	pub const MaxNameLength: u32 = 16;
+	pub const MaxUriLength: u32 = 64;
+	pub const KittyBirthDelay: u64 = 0;
+	pub const MaxBirthsPerBlock: u32 = 10;
+	pub const MaxBatchSize: u32 = 10;
+	pub const MaxChildren: u32 = 10;
+	pub const FractionalModuleId: ModuleId = ModuleId(*b"py/fract");
+}
+
+impl system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl balances::Trait for Test {
+	type Balance = u64;
+	type MaxLocks = ();
+	type Event = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = system::Module<Test>;
+	type WeightInfo = ();
+}
+
+impl pallet_kitties::Trait for Test {
+	type Event = ();
+	type KittyIndex = u32;
+	type Currency = Balances;
+	type Randomness = pallet_randomness_collective_flip::Module<Test>;
+	type KittyDeposit = KittyDeposit;
+	type BreedingStrategy = pallet_kitties::SelectorBreeding;
+	type MaxAskBookWalk = MaxAskBookWalk;
+	type MaxLeaseExpiries = MaxLeaseExpiries;
+	type BreederRoyalty = BreederRoyalty;
+	type MaxKittiesPerAccount = MaxKittiesPerAccount;
+	type MaxMintsPerBlock = MaxMintsPerBlock;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type MaxNameLength = MaxNameLength;
+	type MaxUriLength = MaxUriLength;
+	type BirthDelay = KittyBirthDelay;
+	type Genes = pallet_kitties::KittyGenes;
+	type MaxBirthsPerBlock = MaxBirthsPerBlock;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxChildren = MaxChildren;
+	type OnKittyBurned = ();
+	type WeightInfo = ();
+}
+
+impl Trait for Test {
+	type Event = ();
+	type ModuleId = FractionalModuleId;
+}
+
+pub type Balances = balances::Module<Test>;
+pub type KittiesModule = pallet_kitties::Module<Test>;
+pub type FractionalModule = Module<Test>;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with_kitties(vec![])
+}
+
+/// Genesis kitties have no deposit. (owner, dna, price)
+pub fn new_test_ext_with_kitties(kitties: Vec<(u64, [u8; 16], Option<u64>)>) -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1000), (2, 1000), (3, 1000), (4, 1000)],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_kitties::GenesisConfig::<Test> {
+		kitties,
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	// The randomness of block 0 is not defined
+	ext.execute_with(|| system::Module::<Test>::set_block_number(1));
+	ext
+}
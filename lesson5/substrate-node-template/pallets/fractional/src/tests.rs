@@ -0,0 +1,179 @@
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_noop};
+use super::*;
+
+// Account 1 fractionalizes kitty 0 into 100 shares with a reserve price of 500
+fn fractionalize_kitty() {
+	assert_ok!(KittiesModule::create(Origin::signed(1)));
+	assert_ok!(FractionalModule::fractionalize(Origin::signed(1), 0, 100, 500));
+}
+
+#[test]
+fn fractionalize_locks_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::create(Origin::signed(1)));
+		assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None));
+
+		assert_noop!(FractionalModule::fractionalize(Origin::signed(1), 0, 0, 500), Error::<Test>::ZeroShares);
+		assert_noop!(
+			FractionalModule::fractionalize(Origin::signed(2), 0, 100, 500),
+			pallet_kitties::Error::<Test, pallet_kitties::DefaultInstance>::RequireOwner
+		);
+		assert_ok!(FractionalModule::fractionalize(Origin::signed(1), 0, 100, 500));
+
+		let vault = FractionalModule::vault_account(0);
+		assert_eq!(KittiesModule::kitty_owner(0), Some(vault));
+		assert_eq!(KittiesModule::kitty_price(0), None);
+		assert_eq!(Balances::reserved_balance(vault), 100);
+		assert_eq!(FractionalModule::shares(0, 1), 100);
+		assert_eq!(FractionalModule::vault(0), Some(Vault { owner: 1, total_shares: 100, reserve_price: 500 }));
+
+		assert_noop!(KittiesModule::transfer(Origin::signed(1), 2, 0), pallet_kitties::Error::<Test, pallet_kitties::DefaultInstance>::RequireOwner);
+		assert_noop!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None), pallet_kitties::Error::<Test, pallet_kitties::DefaultInstance>::RequireOwner);
+		assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), pallet_kitties::Error::<Test, pallet_kitties::DefaultInstance>::RequireOwner);
+	})
+}
+
+#[test]
+fn transfer_shares_works() {
+	new_test_ext().execute_with(|| {
+		fractionalize_kitty();
+
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 40));
+		assert_eq!(FractionalModule::shares(0, 1), 60);
+		assert_eq!(FractionalModule::shares(0, 2), 40);
+
+		assert_noop!(
+			FractionalModule::transfer_shares(Origin::signed(2), 0, 3, 41),
+			Error::<Test>::InsufficientShares
+		);
+		assert_noop!(
+			FractionalModule::transfer_shares(Origin::signed(2), 1, 3, 1),
+			Error::<Test>::NotFractionalized
+		);
+	})
+}
+
+#[test]
+fn redeem_requires_all_shares() {
+	new_test_ext().execute_with(|| {
+		fractionalize_kitty();
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 40));
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(3), 0, 500));
+
+		assert_noop!(FractionalModule::redeem(Origin::signed(1), 0), Error::<Test>::RequireAllShares);
+
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 60));
+		assert_ok!(FractionalModule::redeem(Origin::signed(2), 0));
+
+		assert_eq!(KittiesModule::kitty_owner(0), Some(2));
+		assert_eq!(Balances::reserved_balance(2), 100);
+		assert_eq!(FractionalModule::vault(0), None);
+		assert_eq!(FractionalModule::shares(0, 2), 0);
+		// The pending buyout is released
+		assert_eq!(FractionalModule::buyout(0), None);
+		assert_eq!(Balances::reserved_balance(3), 0);
+	})
+}
+
+#[test]
+fn higher_buyout_replaces_pending_one() {
+	new_test_ext().execute_with(|| {
+		fractionalize_kitty();
+
+		assert_noop!(FractionalModule::propose_buyout(Origin::signed(3), 0, 499), Error::<Test>::PriceBelowReserve);
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(3), 0, 500));
+		assert_eq!(Balances::reserved_balance(3), 500);
+
+		assert_noop!(FractionalModule::propose_buyout(Origin::signed(4), 0, 500), Error::<Test>::RequireHigherPrice);
+		assert_noop!(FractionalModule::cancel_buyout(Origin::signed(4), 0), Error::<Test>::RequireBuyer);
+	})
+}
+
+#[test]
+fn cancel_buyout_releases_price_and_votes() {
+	new_test_ext().execute_with(|| {
+		fractionalize_kitty();
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 60));
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(3), 0, 500));
+		assert_ok!(FractionalModule::approve_buyout(Origin::signed(1), 0));
+
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(4), 0, 600));
+
+		assert_eq!(Balances::reserved_balance(3), 0);
+		assert_eq!(Balances::reserved_balance(4), 600);
+		assert_eq!(FractionalModule::buyout_vote(0, 1), 0);
+		assert_eq!(FractionalModule::buyout(0).map(|buyout| buyout.approvals), Some(0));
+
+		assert_ok!(FractionalModule::cancel_buyout(Origin::signed(4), 0));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_noop!(FractionalModule::approve_buyout(Origin::signed(1), 0), Error::<Test>::NoBuyout);
+	})
+}
+
+#[test]
+fn approved_buyout_pays_holders() {
+	new_test_ext().execute_with(|| {
+		fractionalize_kitty();
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 40));
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 3, 30));
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(4), 0, 600));
+
+		assert_ok!(FractionalModule::approve_buyout(Origin::signed(2), 0));
+		assert_eq!(FractionalModule::buyout(0).map(|buyout| buyout.approvals), Some(40));
+
+		// Transferred shares do not approve twice
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(2), 0, 3, 20));
+		assert_eq!(FractionalModule::buyout(0).map(|buyout| buyout.approvals), Some(20));
+		assert_ok!(FractionalModule::approve_buyout(Origin::signed(1), 0));
+		assert_eq!(KittiesModule::kitty_owner(0), Some(FractionalModule::vault_account(0)));
+
+		assert_noop!(FractionalModule::claim_proceeds(Origin::signed(1), 0), Error::<Test>::NotBoughtOut);
+		assert_ok!(FractionalModule::approve_buyout(Origin::signed(3), 0));
+
+		assert_eq!(KittiesModule::kitty_owner(0), Some(4));
+		assert_eq!(Balances::reserved_balance(4), 100);
+		assert_eq!(Balances::free_balance(4), 1000 - 600);
+		assert_eq!(FractionalModule::buyout(0), None);
+		// The breeder royalty goes to account 1
+		assert_eq!(FractionalModule::proceeds(0), Some(Proceeds { remaining: 540, remaining_shares: 100 }));
+		assert_noop!(FractionalModule::redeem(Origin::signed(1), 0), Error::<Test>::AlreadyBoughtOut);
+
+		assert_ok!(FractionalModule::claim_proceeds(Origin::signed(1), 0));
+		assert_ok!(FractionalModule::claim_proceeds(Origin::signed(2), 0));
+		assert_noop!(FractionalModule::claim_proceeds(Origin::signed(2), 0), Error::<Test>::InsufficientShares);
+		assert_ok!(FractionalModule::claim_proceeds(Origin::signed(3), 0));
+
+		assert_eq!(Balances::free_balance(1), 1000 - 100 + 60 + 162);
+		assert_eq!(Balances::free_balance(2), 1000 + 108);
+		assert_eq!(Balances::free_balance(3), 1000 + 270);
+		assert_eq!(FractionalModule::proceeds(0), None);
+		assert_eq!(FractionalModule::vault(0), None);
+	})
+}
+
+#[test]
+fn buyout_of_kitty_without_deposit_works() {
+	new_test_ext_with_kitties(vec![(1, [0u8; 16], None)]).execute_with(|| {
+		let vault = FractionalModule::vault_account(0);
+		assert_eq!(KittiesModule::kitty_deposit(0), 0);
+		assert_ok!(FractionalModule::fractionalize(Origin::signed(1), 0, 100, 500));
+		assert_ok!(FractionalModule::transfer_shares(Origin::signed(1), 0, 2, 40));
+		assert_eq!(Balances::total_balance(&vault), 0);
+
+		assert_ok!(FractionalModule::propose_buyout(Origin::signed(4), 0, 500));
+		assert_ok!(FractionalModule::approve_buyout(Origin::signed(1), 0));
+
+		assert_eq!(KittiesModule::kitty_owner(0), Some(4));
+		assert_eq!(Balances::free_balance(4), 1000 - 500);
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(FractionalModule::proceeds(0), Some(Proceeds { remaining: 450, remaining_shares: 100 }));
+
+		assert_ok!(FractionalModule::claim_proceeds(Origin::signed(1), 0));
+		assert_ok!(FractionalModule::claim_proceeds(Origin::signed(2), 0));
+		assert_eq!(Balances::free_balance(1), 1000 + 50 + 270);
+		assert_eq!(Balances::free_balance(2), 1000 + 180);
+		assert_eq!(Balances::total_balance(&vault), 0);
+	})
+}
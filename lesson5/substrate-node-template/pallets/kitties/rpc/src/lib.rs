@@ -0,0 +1,118 @@
+//! RPC interface for the kitties pallet.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_runtime_api::{KittiesApi as KittiesRuntimeApi, DecodedDna};
+
+#[rpc]
+pub trait KittiesApi<BlockHash, AccountId, KittyIndex, Balance> {
+	/// Decode the traits of a kitty from its DNA.
+	#[rpc(name = "kitties_decodeDna")]
+	fn decode_dna(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<DecodedDna>>;
+
+	/// Kitties owned by an account, at most `count` following `start_after`.
+	#[rpc(name = "kitties_ownedBy")]
+	fn owned_by(
+		&self,
+		account: AccountId,
+		start_after: Option<KittyIndex>,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<KittyIndex>>;
+
+	/// The `count` cheapest kitties for sale with their price.
+	#[rpc(name = "kitties_cheapestAsks")]
+	fn cheapest_asks(&self, count: u32, at: Option<BlockHash>) -> Result<Vec<(KittyIndex, Balance)>>;
+}
+
+/// A struct that implements the `KittiesApi`.
+pub struct Kitties<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Kitties<C, B> {
+	/// Create new `Kitties` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId, KittyIndex, Balance>
+	KittiesApi<<Block as BlockT>::Hash, AccountId, KittyIndex, Balance> for Kitties<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: KittiesRuntimeApi<Block, AccountId, KittyIndex, Balance>,
+	AccountId: Codec,
+	KittyIndex: Codec,
+	Balance: Codec,
+{
+	fn decode_dna(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<DecodedDna>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.decode_dna(&at, kitty_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to decode kitty dna.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn owned_by(
+		&self,
+		account: AccountId,
+		start_after: Option<KittyIndex>,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<KittyIndex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.owned_kitties(&at, account, start_after, count).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query owned kitties.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn cheapest_asks(
+		&self,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(KittyIndex, Balance)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.cheapest_asks(&at, count).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query cheapest asks.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
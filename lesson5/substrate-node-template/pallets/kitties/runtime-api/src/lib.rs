@@ -0,0 +1,27 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+pub use pallet_kitties::{DecodedDna, KittyInfo};
+
+sp_api::decl_runtime_apis! {
+	pub trait KittiesApi<AccountId, KittyIndex, Balance> where
+		AccountId: Codec,
+		KittyIndex: Codec,
+		Balance: Codec,
+	{
+		/// Decode the traits of a kitty from its DNA. None if the kitty does not exist.
+		fn decode_dna(kitty_id: KittyIndex) -> Option<DecodedDna>;
+
+		/// Kitties owned by an account, at most `count` following `start_after`.
+		fn owned_kitties(account: AccountId, start_after: Option<KittyIndex>, count: u32) -> Vec<KittyIndex>;
+
+		/// The `count` cheapest kitties for sale with their price.
+		fn cheapest_asks(count: u32) -> Vec<(KittyIndex, Balance)>;
+
+		/// Owner, price, DNA and metadata of a kitty. None if the kitty does not exist.
+		fn kitty_info(kitty_id: KittyIndex) -> Option<KittyInfo<AccountId, Balance>>;
+	}
+}
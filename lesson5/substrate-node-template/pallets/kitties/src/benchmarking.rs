@@ -0,0 +1,246 @@
+//! Benchmarks of the pallet_kitties extrinsics priced by `WeightInfo`.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::{benchmarks_instance, account, whitelisted_caller};
+use frame_system::RawOrigin;
+use sp_std::prelude::*;
+
+use crate::Module as Kitties;
+
+const SEED: u32 = 0;
+
+/// Give `owner` `n` kitties with their deposit reserved.
+fn create_kitties<T: Trait<I>, I: Instance>(owner: &T::AccountId, n: u32) -> Vec<T::KittyIndex> {
+	T::Currency::make_free_balance_be(owner, BalanceOf::<T, I>::max_value() / 2u32.into());
+	(0..n).map(|i| {
+		let kitty_id = Kitties::<T, I>::allocate_kitty_id().expect("Benchmark kitty ids do not overflow; qed");
+		T::Currency::reserve(owner, T::KittyDeposit::get()).expect("Owner is funded; qed");
+		Kitties::<T, I>::insert_kitty(owner, kitty_id, Kitty([i as u8; 16]), T::KittyDeposit::get())
+			.expect("Kitty ids are unique; qed");
+		kitty_id
+	}).collect()
+}
+
+benchmarks_instance! {
+	_ { }
+
+	create {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+	}: _(RawOrigin::Signed(caller))
+	verify {
+		assert_eq!(Kitties::<T, I>::kitties_count(), 1u32.into());
+	}
+
+	breed {
+		let caller: T::AccountId = whitelisted_caller();
+		let parents = create_kitties::<T, I>(&caller, 2);
+	}: _(RawOrigin::Signed(caller), parents[0], parents[1])
+	verify {
+		assert_eq!(Kitties::<T, I>::breed_count(parents[0]), 1);
+	}
+
+	claim_kitty {
+		let caller: T::AccountId = whitelisted_caller();
+		let parents = create_kitties::<T, I>(&caller, 2);
+		let kitty_id = Kitties::<T, I>::allocate_kitty_id()?;
+		T::Currency::reserve(&caller, T::KittyDeposit::get())?;
+		PendingBirths::<T, I>::insert(kitty_id, PendingBirth {
+			owner: caller.clone(),
+			parents: Some(BreedingPair { kitty_id_1: parents[0], kitty_id_2: parents[1], dna_1: [0; 16], dna_2: [1; 16] }),
+			deposit: T::KittyDeposit::get(),
+			reveal_at: frame_system::Module::<T>::block_number(),
+			seed: Some(Default::default()),
+		});
+		PendingBirthCount::<T, I>::insert(&caller, 1);
+	}: _(RawOrigin::Signed(caller.clone()), kitty_id)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_owner(kitty_id), Some(caller));
+	}
+
+	transfer {
+		let caller: T::AccountId = whitelisted_caller();
+		let to: T::AccountId = account("to", 0, SEED);
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+	}: _(RawOrigin::Signed(caller), to.clone(), kitty_id)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_owner(kitty_id), Some(to));
+	}
+
+	transfer_batch {
+		let n in 1 .. T::MaxBatchSize::get();
+		let caller: T::AccountId = whitelisted_caller();
+		let to: T::AccountId = account("to", 0, SEED);
+		let kitty_ids = create_kitties::<T, I>(&caller, n);
+	}: _(RawOrigin::Signed(caller), to.clone(), kitty_ids)
+	verify {
+		assert_eq!(<OwnedKittiesList<T, I>>::len(&to), n);
+	}
+
+	gift {
+		let caller: T::AccountId = whitelisted_caller();
+		let to: T::AccountId = account("to", 0, SEED);
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+	}: _(RawOrigin::Signed(caller), to.clone(), kitty_id, Default::default())
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_owner(kitty_id), Some(to));
+	}
+
+	ask {
+		// The hint is followed by more cheaper asks than MaxAskBookWalk
+		let walk = T::MaxAskBookWalk::get();
+		let seller: T::AccountId = account("seller", 0, SEED);
+		let listed = create_kitties::<T, I>(&seller, walk + 1);
+		let mut previous = None;
+		for kitty_id in listed.iter() {
+			Kitties::<T, I>::do_ask(&seller, *kitty_id, Some(1u32.into()), previous)?;
+			previous = Some(*kitty_id);
+		}
+		let caller: T::AccountId = whitelisted_caller();
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+	}: _(RawOrigin::Signed(caller), kitty_id, Some(2u32.into()), Some(listed[0]))
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_price(kitty_id), Some(2u32.into()));
+	}
+
+	ask_batch {
+		let n in 1 .. T::MaxBatchSize::get();
+		// The book has more cheaper asks than MaxAskBookWalk, each ask of the batch walks
+		// the longest allowed run of them from its hint
+		let walk = T::MaxAskBookWalk::get();
+		let seller: T::AccountId = account("seller", 0, SEED);
+		let listed = create_kitties::<T, I>(&seller, n * (walk + 1));
+		let mut previous = None;
+		for (i, kitty_id) in listed.iter().enumerate() {
+			let price = 2 * (i as u32 / (walk + 1)) + 1;
+			Kitties::<T, I>::do_ask(&seller, *kitty_id, Some(price.into()), previous)?;
+			previous = Some(*kitty_id);
+		}
+		let caller: T::AccountId = whitelisted_caller();
+		let asks = create_kitties::<T, I>(&caller, n).into_iter()
+			.enumerate()
+			.map(|(i, kitty_id)| {
+				let hint = listed[i * (walk + 1) as usize];
+				(kitty_id, Some((2 * i as u32 + 2).into()), Some(hint))
+			})
+			.collect::<Vec<_>>();
+		let last = asks[asks.len() - 1].0;
+	}: _(RawOrigin::Signed(caller), asks)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_price(last), Some((2 * n).into()));
+	}
+
+	buy {
+		// The breeder is neither the seller nor the buyer and gets a royalty
+		let breeder: T::AccountId = account("breeder", 0, SEED);
+		let kitty_id = create_kitties::<T, I>(&breeder, 1)[0];
+		let seller: T::AccountId = account("seller", 0, SEED);
+		Kitties::<T, I>::transfer(RawOrigin::Signed(breeder).into(), seller.clone(), kitty_id)?;
+		let price = T::Currency::minimum_balance() * 100u32.into();
+		Kitties::<T, I>::do_ask(&seller, kitty_id, Some(price), None)?;
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+	}: _(RawOrigin::Signed(caller.clone()), kitty_id, price)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_owner(kitty_id), Some(caller));
+	}
+
+	burn {
+		let c in 0 .. T::MaxChildren::get();
+		// The kitty has parents, metadata, an ask and `c` children to clean up
+		let caller: T::AccountId = whitelisted_caller();
+		let kitty_ids = create_kitties::<T, I>(&caller, 3);
+		let kitty_id = kitty_ids[2];
+		KittyParents::<T, I>::insert(kitty_id, (kitty_ids[0], kitty_ids[1]));
+		KittyChildren::<T, I>::insert(kitty_ids[0], kitty_id, ());
+		KittyChildren::<T, I>::insert(kitty_ids[1], kitty_id, ());
+		for i in 0 .. c {
+			KittyChildren::<T, I>::insert(kitty_id, T::KittyIndex::from(1000 + i), ());
+		}
+		BreedCounts::<T, I>::insert(kitty_id, c);
+		let name = vec![0u8; T::MaxNameLength::get() as usize];
+		let uri = vec![0u8; T::MaxUriLength::get() as usize];
+		Kitties::<T, I>::set_metadata(RawOrigin::Signed(caller.clone()).into(), kitty_id, name, uri)?;
+		Kitties::<T, I>::do_ask(&caller, kitty_id, Some(1u32.into()), None)?;
+	}: _(RawOrigin::Signed(caller), kitty_id)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_owner(kitty_id), None);
+		assert_eq!(KittyChildren::<T, I>::iter_prefix(kitty_id).count(), 0);
+	}
+
+	set_metadata {
+		// The previous metadata is refunded first
+		let caller: T::AccountId = whitelisted_caller();
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+		let name = vec![0u8; T::MaxNameLength::get() as usize];
+		let uri = vec![0u8; T::MaxUriLength::get() as usize];
+		Kitties::<T, I>::set_metadata(RawOrigin::Signed(caller.clone()).into(), kitty_id, name.clone(), uri.clone())?;
+	}: _(RawOrigin::Signed(caller), kitty_id, name.clone(), uri)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_metadata(kitty_id).map(|metadata| metadata.name), Some(name));
+	}
+
+	clear_metadata {
+		let caller: T::AccountId = whitelisted_caller();
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+		let name = vec![0u8; T::MaxNameLength::get() as usize];
+		let uri = vec![0u8; T::MaxUriLength::get() as usize];
+		Kitties::<T, I>::set_metadata(RawOrigin::Signed(caller.clone()).into(), kitty_id, name, uri)?;
+	}: _(RawOrigin::Signed(caller), kitty_id)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_metadata(kitty_id), None);
+	}
+
+	lease {
+		let caller: T::AccountId = whitelisted_caller();
+		let to: T::AccountId = account("to", 0, SEED);
+		let kitty_id = create_kitties::<T, I>(&caller, 1)[0];
+		let until = frame_system::Module::<T>::block_number() + 10u32.into();
+	}: _(RawOrigin::Signed(caller), kitty_id, to.clone(), 1u32.into(), until)
+	verify {
+		assert_eq!(Kitties::<T, I>::lease_offer(kitty_id).map(|offer| offer.lessee), Some(to));
+	}
+
+	accept_lease {
+		// The kitty is for sale, accepting the lease delists it
+		let owner: T::AccountId = account("owner", 0, SEED);
+		let kitty_id = create_kitties::<T, I>(&owner, 1)[0];
+		Kitties::<T, I>::do_ask(&owner, kitty_id, Some(1u32.into()), None)?;
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T, I>::max_value() / 2u32.into());
+		let until = frame_system::Module::<T>::block_number() + 10u32.into();
+		Kitties::<T, I>::lease(RawOrigin::Signed(owner).into(), kitty_id, caller.clone(), 1u32.into(), until)?;
+	}: _(RawOrigin::Signed(caller.clone()), kitty_id)
+	verify {
+		assert_eq!(Kitties::<T, I>::kitty_lease(kitty_id).map(|offer| offer.lessee), Some(caller));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn benchmarks_run_on_the_mock() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_create::<Test>());
+			assert_ok!(test_benchmark_breed::<Test>());
+			assert_ok!(test_benchmark_claim_kitty::<Test>());
+			assert_ok!(test_benchmark_transfer::<Test>());
+			assert_ok!(test_benchmark_transfer_batch::<Test>());
+			assert_ok!(test_benchmark_gift::<Test>());
+			assert_ok!(test_benchmark_ask::<Test>());
+			assert_ok!(test_benchmark_ask_batch::<Test>());
+			assert_ok!(test_benchmark_buy::<Test>());
+			assert_ok!(test_benchmark_burn::<Test>());
+			assert_ok!(test_benchmark_set_metadata::<Test>());
+			assert_ok!(test_benchmark_clear_metadata::<Test>());
+			assert_ok!(test_benchmark_lease::<Test>());
+			assert_ok!(test_benchmark_accept_lease::<Test>());
+		});
+	}
+}
@@ -0,0 +1,90 @@
+use codec::Encode;
+use frame_support::traits::Get;
+use sp_io::hashing::blake2_256;
+use sp_runtime::Permill;
+use crate::dna::{Genes, RECESSIVE_OFFSET};
+
+/// How the DNA of a new kitty is derived from its parents.
+pub trait BreedingStrategy {
+	/// Combine the DNA of two parents with the gene layout `genes` of their collection,
+	/// all randomness must come from `seed`.
+	fn breed(dna1: &[u8; 16], dna2: &[u8; 16], seed: &[u8; 16], genes: &Genes) -> [u8; 16];
+}
+
+fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
+	(selector & dna1) | (!selector & dna2)
+}
+
+/// Pick each bit from one of the parents using the seed as selector.
+pub struct SelectorBreeding;
+
+impl BreedingStrategy for SelectorBreeding {
+	fn breed(dna1: &[u8; 16], dna2: &[u8; 16], seed: &[u8; 16], _genes: &Genes) -> [u8; 16] {
+		let mut new_dna = [0u8; 16];
+
+		// Combine parents and selector to create new kitty
+		for i in 0..dna1.len() {
+			new_dna[i] = combine_dna(dna1[i], dna2[i], seed[i]);
+		}
+
+		new_dna
+	}
+}
+
+/// Alleles with the high bit set are dominant.
+const DOMINANT_MASK: u8 = 0b1000_0000;
+
+/// Each trait has an expressed allele at the gene byte and a recessive allele at
+/// `gene byte + RECESSIVE_OFFSET`. Each parent passes one of its two alleles, the
+/// dominant one is expressed, and every passed allele may mutate with `MutationRate`.
+/// Bytes that are not part of a trait of the collection fall back to `SelectorBreeding`,
+/// as do genes at or after `RECESSIVE_OFFSET` which have no room for a recessive allele.
+pub struct MendelianBreeding<MutationRate>(sp_std::marker::PhantomData<MutationRate>);
+
+impl<MutationRate: Get<Permill>> MendelianBreeding<MutationRate> {
+	fn inherit(dna: &[u8; 16], index: usize, pick_recessive: bool, mutation: (u32, u8)) -> u8 {
+		let allele = if pick_recessive { dna[index + RECESSIVE_OFFSET] } else { dna[index] };
+		let (roll, mutated) = mutation;
+		if roll % 1_000_000 < MutationRate::get().deconstruct() {
+			mutated
+		} else {
+			allele
+		}
+	}
+}
+
+impl<MutationRate: Get<Permill>> BreedingStrategy for MendelianBreeding<MutationRate> {
+	fn breed(dna1: &[u8; 16], dna2: &[u8; 16], seed: &[u8; 16], genes: &Genes) -> [u8; 16] {
+		let mut new_dna = SelectorBreeding::breed(dna1, dna2, seed, genes);
+
+		for &(index, _) in genes.iter().filter(|(index, _)| *index < RECESSIVE_OFFSET) {
+			// Fresh randomness for every trait, derived from the seed only
+			let random = (seed, index as u8).using_encoded(blake2_256);
+			let roll = |offset: usize| u32::from_le_bytes([
+				random[offset], random[offset + 1], random[offset + 2], random[offset + 3],
+			]);
+
+			let allele1 = Self::inherit(dna1, index, random[0] & 1 == 1, (roll(4), random[8]));
+			let allele2 = Self::inherit(dna2, index, random[0] & 2 == 2, (roll(12), random[16]));
+
+			let allele1_dominant = allele1 & DOMINANT_MASK != 0;
+			let allele2_dominant = allele2 & DOMINANT_MASK != 0;
+			let allele1_expressed = if allele1_dominant == allele2_dominant {
+				// Co-dominant, pick one of them
+				random[0] & 4 == 4
+			} else {
+				allele1_dominant
+			};
+
+			let (expressed, recessive) = if allele1_expressed {
+				(allele1, allele2)
+			} else {
+				(allele2, allele1)
+			};
+			new_dna[index] = expressed;
+			new_dna[index + RECESSIVE_OFFSET] = recessive;
+		}
+
+		new_dna
+	}
+}
@@ -0,0 +1,95 @@
+//! Default weights of the pallet_kitties extrinsics priced by `WeightInfo`.
+//!
+//! These are estimates, not benchmark results. The storage reads and writes are counted from
+//! the code, `ask` and `ask_batch` walk 20 asks per kitty as allowed by `MaxAskBookWalk` in the
+//! node runtime, and `burn` removes one children entry per child. The execution times are rough
+//! guesses from the number of storage accesses and the balance operations of each extrinsic.
+//!
+//! Replace this file by the results of the benchmarks in `benchmarking.rs` on the reference
+//! hardware, generated with:
+//!
+//! ./target/release/node-template benchmark --chain dev --execution wasm --wasm-execution compiled
+//!     --pallet pallet_kitties --extrinsic '*' --steps 50 --repeat 20
+//!     --output ./pallets/kitties/src/default_weights.rs
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+impl crate::WeightInfo for () {
+	fn create() -> Weight {
+		(52_381_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+	}
+	fn breed() -> Weight {
+		(66_904_000 as Weight)
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().writes(13 as Weight))
+	}
+	fn claim_kitty() -> Weight {
+		(81_240_000 as Weight)
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().writes(14 as Weight))
+	}
+	fn transfer() -> Weight {
+		(61_310_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+	}
+	fn transfer_batch(n: u32) -> Weight {
+		(26_571_000 as Weight)
+			.saturating_add((37_964_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads((10 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((9 as Weight).saturating_mul(n as Weight)))
+	}
+	fn gift() -> Weight {
+		(72_585_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+	}
+	fn ask() -> Weight {
+		(59_097_000 as Weight)
+			.saturating_add(DbWeight::get().reads(50 as Weight))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	fn ask_batch(n: u32) -> Weight {
+		(0 as Weight)
+			.saturating_add((59_097_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads((50 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((7 as Weight).saturating_mul(n as Weight)))
+	}
+	fn buy() -> Weight {
+		(104_622_000 as Weight)
+			.saturating_add(DbWeight::get().reads(17 as Weight))
+			.saturating_add(DbWeight::get().writes(16 as Weight))
+	}
+	fn burn(c: u32) -> Weight {
+		(96_310_000 as Weight)
+			.saturating_add((2_847_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(21 as Weight))
+			.saturating_add(DbWeight::get().writes(22 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(c as Weight)))
+	}
+	fn set_metadata() -> Weight {
+		(48_772_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn clear_metadata() -> Weight {
+		(37_015_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn lease() -> Weight {
+		(24_903_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn accept_lease() -> Weight {
+		(70_458_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
+	}
+}
@@ -0,0 +1,83 @@
+use codec::{Encode, Decode};
+use frame_support::traits::Get;
+use sp_runtime::RuntimeDebug;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// Gene layout of the kitty DNA: (dna byte, number of variants) for each trait.
+/// Keep in sync with the avatar parts of the frontend.
+pub const BODY_GENE: (usize, u8) = (0, 15);
+pub const EYES_GENE: (usize, u8) = (1, 15);
+pub const ACCESSORY_GENE: (usize, u8) = (2, 20);
+pub const COLOUR_GENE: (usize, u8) = (3, 10);
+pub const MOUTH_GENE: (usize, u8) = (4, 10);
+
+/// All the genes, the position is used as trait kind in `TraitCounts`.
+pub const GENES: [(usize, u8); 5] = [BODY_GENE, EYES_GENE, ACCESSORY_GENE, COLOUR_GENE, MOUTH_GENE];
+
+/// Gene layout of a collection, in the same order as `GENES`.
+/// Gene bytes must be below `RECESSIVE_OFFSET` and have at least one variant.
+pub type Genes = [(usize, u8); 5];
+
+/// The gene layout of the kitties, `GENES`.
+pub struct KittyGenes;
+
+impl Get<Genes> for KittyGenes {
+	fn get() -> Genes {
+		GENES
+	}
+}
+
+/// The recessive allele of each trait is stored this many bytes after its gene.
+pub const RECESSIVE_OFFSET: usize = 8;
+
+/// Whether all gene bytes are below `RECESSIVE_OFFSET` and have at least one variant.
+pub fn is_valid_layout(genes: &Genes) -> bool {
+	genes.iter().all(|&(index, variants)| index < RECESSIVE_OFFSET && variants > 0)
+}
+
+/// Scale of the rarity score of a single trait.
+pub const RARITY_SCALE: u64 = 100;
+
+/// Traits decoded from a kitty DNA, each value is the variant index of the trait.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq, Default)]
+pub struct KittyTraits {
+	pub body: u8,
+	pub eyes: u8,
+	pub accessory: u8,
+	pub colour: u8,
+	pub mouth: u8,
+}
+
+impl KittyTraits {
+	pub fn decode_dna(dna: &[u8; 16], genes: &Genes) -> Self {
+		// A gene outside of the DNA or without variants has a single variant
+		let gene = |(index, variants): (usize, u8)| {
+			dna.get(index).and_then(|byte| byte.checked_rem(variants)).unwrap_or(0)
+		};
+		KittyTraits {
+			body: gene(genes[0]),
+			eyes: gene(genes[1]),
+			accessory: gene(genes[2]),
+			colour: gene(genes[3]),
+			mouth: gene(genes[4]),
+		}
+	}
+
+	/// Variants in the same order as `GENES`.
+	pub fn variants(&self) -> [u8; 5] {
+		[self.body, self.eyes, self.accessory, self.colour, self.mouth]
+	}
+}
+
+/// Decoded DNA of a kitty returned by the runtime api.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq, Default)]
+pub struct DecodedDna {
+	pub dna: [u8; 16],
+	pub traits: KittyTraits,
+	/// Sum of the rarity of each trait, higher is rarer.
+	pub rarity_score: u64,
+}
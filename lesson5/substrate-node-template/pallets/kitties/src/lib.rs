@@ -2,41 +2,210 @@
 
 use codec::{Encode, Decode};
 use frame_support::{
-	decl_module, decl_storage, decl_error, decl_event, ensure, StorageValue, StorageMap, Parameter,
-	traits::{Randomness, Currency, ExistenceRequirement},
+	decl_module, decl_storage, decl_error, decl_event, ensure, StorageValue, StorageMap, StorageDoubleMap, Parameter,
+	traits::{Randomness, Currency, ReservableCurrency, ExistenceRequirement, BalanceStatus, Get},
+	dispatch::DispatchResult, weights::Weight,
+	storage::{with_transaction, TransactionOutcome},
 };
 use sp_io::hashing::blake2_128;
+use sp_std::prelude::*;
 use frame_system::{self as system, ensure_signed};
-use sp_runtime::{DispatchError, RuntimeDebug, traits::{AtLeast32Bit, Member, Bounded,}};
-use crate::link::{LinkedList, LinkedItem};
+use sp_runtime::{DispatchError, RuntimeDebug, SaturatedConversion, Permill, traits::{AtLeast32Bit, Member, Bounded, Convert, Saturating, Zero}};
+use linked_list::{LinkedList, LinkedItem, LinkedListError, MapLayout, SortedLinkedList};
+pub use crate::dna::{KittyTraits, DecodedDna, Genes, KittyGenes};
+pub use crate::breeding::{BreedingStrategy, SelectorBreeding, MendelianBreeding};
+pub use crate::migration::Releases;
+
+pub mod dna;
+pub mod breeding;
+pub mod migration;
+mod default_weights;
+mod benchmarking;
+
+/// Run `f` in a storage transaction, discarding its changes if it fails.
+///
+/// `#[transactional]` of frame-support 2.0.1 does not support `?` in the function body.
+pub fn with_transaction_result<R>(f: impl FnOnce() -> Result<R, DispatchError>) -> Result<R, DispatchError> {
+	with_transaction(|| {
+		let result = f();
+		if result.is_ok() {
+			TransactionOutcome::Commit(result)
+		} else {
+			TransactionOutcome::Rollback(result)
+		}
+	})
+}
+
+/// The 16 bytes of kitty DNA.
+pub type Dna = [u8; 16];
+
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct Kitty(pub Dna);
+
+/// Name and URI of a kitty, with the deposit reserved for their bytes.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Default)]
+pub struct KittyMetadata<Balance> {
+	pub name: Vec<u8>,
+	/// URI of an image or a bio.
+	pub uri: Vec<u8>,
+	pub deposit: Balance,
+}
+
+/// Everything about a kitty, returned by the runtime api.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct KittyInfo<AccountId, Balance> {
+	pub owner: AccountId,
+	/// None means not for sale.
+	pub price: Option<Balance>,
+	pub dna: DecodedDna,
+	pub name: Vec<u8>,
+	pub uri: Vec<u8>,
+}
+
+/// Parents of a kitty being bred, with their DNA when the breeding was requested.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct BreedingPair<KittyIndex> {
+	pub kitty_id_1: KittyIndex,
+	pub kitty_id_2: KittyIndex,
+	pub dna_1: [u8; 16],
+	pub dna_2: [u8; 16],
+}
 
-mod link;
+/// A kitty waiting for the randomness of block `reveal_at` to get its DNA.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
+pub struct PendingBirth<AccountId, KittyIndex, Balance, BlockNumber, Hash> {
+	pub owner: AccountId,
+	/// None for a created kitty.
+	pub parents: Option<BreedingPair<KittyIndex>>,
+	pub deposit: Balance,
+	pub reveal_at: BlockNumber,
+	/// The randomness of block `reveal_at`, taken when the block is initialized.
+	pub seed: Option<Hash>,
+}
 
+/// Usage rights of a kitty lent to `lessee` until block `until`, for `fee`.
 #[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq)]
-pub struct Kitty(pub [u8; 16]);
+pub struct Lease<AccountId, Balance, BlockNumber> {
+	pub lessee: AccountId,
+	pub fee: Balance,
+	pub until: BlockNumber,
+}
+
+/// Notified of burned kitties, for pallets keeping data about them.
+pub trait OnKittyBurned<KittyIndex> {
+	fn on_kitty_burned(kitty_id: KittyIndex);
+}
+
+impl<KittyIndex> OnKittyBurned<KittyIndex> for () {
+	fn on_kitty_burned(_kitty_id: KittyIndex) {}
+}
+
+pub trait WeightInfo {
+	fn create() -> Weight;
+	fn breed() -> Weight;
+	fn claim_kitty() -> Weight;
+	fn transfer() -> Weight;
+	fn transfer_batch(n: u32) -> Weight;
+	fn gift() -> Weight;
+	fn ask() -> Weight;
+	fn ask_batch(n: u32) -> Weight;
+	fn buy() -> Weight;
+	fn burn(c: u32) -> Weight;
+	fn set_metadata() -> Weight;
+	fn clear_metadata() -> Weight;
+	fn lease() -> Weight;
+	fn accept_lease() -> Weight;
+}
 
-pub trait Trait: frame_system::Trait {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+pub trait Trait<I: Instance = DefaultInstance>: frame_system::Trait {
+	type Event: From<Event<Self, I>> + Into<<Self as frame_system::Trait>::Event>;
 	type KittyIndex: Parameter + Member + AtLeast32Bit + Default + Copy + From<u32>;
-	type Currency: Currency<Self::AccountId>;
+	type Currency: ReservableCurrency<Self::AccountId>;
 	type Randomness: Randomness<Self::Hash>;
+	/// The deposit reserved from the owner for each created or bred kitty.
+	type KittyDeposit: Get<BalanceOf<Self, I>>;
+	/// Which DNA bytes hold the traits of the collection, and their number of variants.
+	type Genes: Get<Genes>;
+	/// How the DNA of a bred kitty is derived from its parents.
+	type BreedingStrategy: BreedingStrategy;
+	/// Maximum number of asks walked from the position hint when listing a kitty.
+	type MaxAskBookWalk: Get<u32>;
+	/// Maximum number of leases ending in the same block.
+	type MaxLeaseExpiries: Get<u32>;
+	/// Share of every sale paid to the breeder of the kitty.
+	type BreederRoyalty: Get<Permill>;
+	/// Maximum number of kitties an account may own to create or breed a kitty.
+	type MaxKittiesPerAccount: Get<u32>;
+	/// Maximum number of kitties created or bred in a block.
+	type MaxMintsPerBlock: Get<u32>;
+	/// The deposit reserved for each byte of the metadata of a kitty.
+	type MetadataDepositPerByte: Get<BalanceOf<Self, I>>;
+	/// Maximum length of the name of a kitty.
+	type MaxNameLength: Get<u32>;
+	/// Maximum length of the URI of a kitty.
+	type MaxUriLength: Get<u32>;
+	/// Blocks between a create or breed and the randomness giving the kitty its DNA.
+	/// Zero generates the DNA in the same extrinsic.
+	type BirthDelay: Get<Self::BlockNumber>;
+	/// Maximum number of pending births finalized in `on_initialize`, the rest wait for the next block.
+	type MaxBirthsPerBlock: Get<u32>;
+	/// Maximum number of kitties in a `transfer_batch` or `ask_batch`.
+	type MaxBatchSize: Get<u32>;
+	/// Maximum number of kitties bred from each kitty, bounds the cleanup of a burned kitty.
+	type MaxChildren: Get<u32>;
+	/// Called when a kitty is burned.
+	type OnKittyBurned: OnKittyBurned<Self::KittyIndex>;
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
 }
 
-type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
-type KittyLinkedItem<T> = LinkedItem<<T as Trait>::KittyIndex>;
-type OwnedKittiesList<T> = LinkedList<OwnedKitties<T>, <T as system::Trait>::AccountId, <T as Trait>::KittyIndex>;
+type BalanceOf<T, I> = <<T as Trait<I>>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type KittyLinkedItem<T, I> = LinkedItem<<T as Trait<I>>::KittyIndex>;
+type PendingBirthOf<T, I> = PendingBirth<
+	<T as system::Trait>::AccountId, <T as Trait<I>>::KittyIndex, BalanceOf<T, I>, <T as system::Trait>::BlockNumber,
+	<T as system::Trait>::Hash,
+>;
+type BatchAskOf<T, I> = (<T as Trait<I>>::KittyIndex, Option<BalanceOf<T, I>>, Option<<T as Trait<I>>::KittyIndex>);
+type LeaseOf<T, I> = Lease<<T as system::Trait>::AccountId, BalanceOf<T, I>, <T as system::Trait>::BlockNumber>;
+type OwnedKittiesList<T, I> = LinkedList<
+	MapLayout<OwnedKitties<T, I>>, <T as system::Trait>::AccountId, <T as Trait<I>>::KittyIndex,
+>;
+type AskBookList<T, I> = SortedLinkedList<
+	MapLayout<AskBook<T, I>>, (), <T as Trait<I>>::KittyIndex, BalanceOf<T, I>, KittyPriceOf<T, I>,
+>;
+
+/// Maximum number of kitties returned by one page of `owned_kitties_page`.
+pub const MAX_OWNED_KITTIES_PAGE: u32 = 100;
+
+/// Maximum number of asks returned by `cheapest_asks`.
+pub const MAX_CHEAPEST_ASKS: u32 = 100;
+
+/// Version of the event schema, see `Event`.
+pub const EVENT_SCHEMA_VERSION: u32 = 2;
+
+/// Orders the asks by the price of the kitty.
+pub struct KittyPriceOf<T, I>(sp_std::marker::PhantomData<(T, I)>);
+
+impl<T: Trait<I>, I: Instance> Convert<T::KittyIndex, BalanceOf<T, I>> for KittyPriceOf<T, I> {
+	fn convert(kitty_id: T::KittyIndex) -> BalanceOf<T, I> {
+		<Module<T, I>>::kitty_price(kitty_id).unwrap_or_else(Zero::zero)
+	}
+}
 
 decl_storage! {
-	trait Store for Module<T: Trait> as Kitties {
+	trait Store for Module<T: Trait<I>, I: Instance = DefaultInstance> as Kitties {
 		/// Stores all the kitties, key is the kitty id / index
 		pub Kitties get(fn kitties): map hasher(blake2_128_concat) T::KittyIndex => Option<Kitty>;
 
 		/// Stores the total number of kitties. i.e. the next kitty index
 		pub KittiesCount get(fn kitties_count): T::KittyIndex;
 
+		/// Stores the number of kitties that are not burned yet.
+		pub LiveKittiesCount get(fn live_kitties_count): T::KittyIndex;
+
 		/// Store owned kitties in a linked list.
 		pub OwnedKitties get(fn owned_kitties): map hasher(blake2_128_concat)
-			(T::AccountId, Option<T::KittyIndex>) => Option<KittyLinkedItem<T>>;
+			(T::AccountId, Option<T::KittyIndex>) => Option<KittyLinkedItem<T, I>>;
 
 		/// Store owner of each kitity.
 		pub KittyOwners get(fn kitty_owner): map hasher(blake2_128_concat) T::KittyIndex =>
@@ -44,122 +213,493 @@ decl_storage! {
 
 		/// Get kitty price. None means not for sale.
 		pub KittyPrices get(fn kitty_price): map hasher(blake2_128_concat) T::KittyIndex =>
-			Option<BalanceOf<T>>;
+			Option<BalanceOf<T, I>>;
+
+		/// All kitties for sale in a linked list sorted by price, cheapest first.
+		pub AskBook get(fn ask_book): map hasher(blake2_128_concat)
+			((), Option<T::KittyIndex>) => Option<KittyLinkedItem<T, I>>;
+
+		/// Deposit reserved for each kitty, moves with the kitty to the new owner.
+		pub KittyDeposits get(fn kitty_deposit): map hasher(blake2_128_concat) T::KittyIndex =>
+			BalanceOf<T, I>;
+
+		/// Number of live kitties for each trait variant. (trait kind, variant) => count
+		/// The trait kind is the position of the gene in `T::Genes`.
+		pub TraitCounts get(fn trait_count): double_map hasher(twox_64_concat) u8,
+			hasher(twox_64_concat) u8 => T::KittyIndex;
+
+		/// Parents of each bred kitty.
+		pub KittyParents get(fn kitty_parents): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<(T::KittyIndex, T::KittyIndex)>;
+
+		/// Children of each kitty. (parent, child) => ()
+		pub KittyChildren get(fn kitty_children): double_map hasher(blake2_128_concat) T::KittyIndex,
+			hasher(blake2_128_concat) T::KittyIndex => ();
+
+		/// Number of kitties bred from each kitty, pending births included.
+		pub BreedCounts get(fn breed_count): map hasher(blake2_128_concat) T::KittyIndex => u32;
+
+		/// Lease offered by the owner of a kitty, waiting for the lessee to accept it.
+		pub LeaseOffers get(fn lease_offer): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<LeaseOf<T, I>>;
+
+		/// Active lease of a kitty. The kitty can not be transferred or sold while leased.
+		pub KittyLeases get(fn kitty_lease): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<LeaseOf<T, I>>;
+
+		/// Leased kitties ending at each block, at most `MaxLeaseExpiries` per block.
+		pub LeaseExpiries get(fn lease_expiries): map hasher(twox_64_concat) T::BlockNumber =>
+			Vec<T::KittyIndex>;
+
+		/// Account that created or bred each kitty, receives the royalty of its sales.
+		pub KittyBreeders get(fn kitty_breeder): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<T::AccountId>;
+
+		/// Price of the last sale of each kitty.
+		pub LastSalePrices get(fn last_sale_price): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<BalanceOf<T, I>>;
+
+		/// Name and URI of each kitty, kept across transfers.
+		pub Metadata get(fn kitty_metadata): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<KittyMetadata<BalanceOf<T, I>>>;
+
+		/// Kitties created or bred waiting for their DNA.
+		pub PendingBirths get(fn pending_birth): map hasher(blake2_128_concat) T::KittyIndex =>
+			Option<PendingBirthOf<T, I>>;
+
+		/// Pending births finalized at each block.
+		pub BirthQueue get(fn birth_queue): map hasher(twox_64_concat) T::BlockNumber => Vec<T::KittyIndex>;
+
+		/// Number of pending births of each account, counted as owned kitties.
+		pub PendingBirthCount get(fn pending_birth_count): map hasher(blake2_128_concat) T::AccountId => u32;
+
+		/// Number of kitties created or bred in the block. (block number, mints)
+		pub BlockMints get(fn block_mints): (T::BlockNumber, u32);
+
+		/// Storage layout version, used to run the migrations.
+		pub StorageVersion get(fn storage_version) build(|_| Releases::V2LinkedList): Releases;
+	}
+	add_extra_genesis {
+		/// Kitties minted at genesis. (owner, dna, price if for sale)
+		config(kitties): Vec<(T::AccountId, [u8; 16], Option<BalanceOf<T, I>>)>;
+		build(|config: &GenesisConfig<T, I>| {
+			for (owner, dna, price) in config.kitties.iter() {
+				let kitty_id = <Module<T, I>>::allocate_kitty_id().expect("Too many genesis kitties");
+				// No deposit is reserved for genesis kitties
+				<Module<T, I>>::insert_kitty(owner, kitty_id, Kitty(*dna), Zero::zero())
+					.expect("Genesis kitty ids are unique; qed");
+				if let Some(price) = price {
+					<KittyPrices<T, I>>::insert(kitty_id, price);
+					<AskBookList<T, I>>::insert(&(), kitty_id).expect("Genesis kitty ids are unique; qed");
+				}
+			}
+		});
 	}
 }
 
 decl_error! {
-	pub enum Error for Module<T: Trait> {
+	pub enum Error for Module<T: Trait<I>, I: Instance> {
 		KittiesCountOverflow,
 		InvalidKittyId,
 		RequireDifferentParent,
 		RequireOwner,
 		NotForSale,
 		PriceTooLow,
+		InvalidAskHint,
+		AskHintTooFar,
+		KittyLeased,
+		NoLeaseOffer,
+		InvalidLessee,
+		LeaseExpired,
+		TooManyLeaseExpiries,
+		/// The account owns `MaxKittiesPerAccount` kitties already.
+		TooManyOwnedKitties,
+		/// `MaxMintsPerBlock` kitties are created or bred in this block already.
+		TooManyMintsInBlock,
+		NameTooLong,
+		UriTooLong,
+		NoPendingBirth,
+		BirthNotDue,
+		/// More than `MaxBatchSize` kitties in a batch.
+		BatchTooLarge,
+		/// A kitty is asked twice in the same batch.
+		DuplicateKitty,
+		/// The owner does not have the deposit of the kitty reserved anymore.
+		DepositNotReserved,
+		/// The kitty is not in the list it should be in.
+		KittyNotInList,
+		/// The kitty is in a list already.
+		KittyAlreadyInList,
+		/// A parent has `MaxChildren` children already.
+		TooManyChildren,
+	}
+}
+
+impl<T: Trait<I>, I: Instance> From<LinkedListError> for Error<T, I> {
+	fn from(e: LinkedListError) -> Self {
+		match e {
+			LinkedListError::ValueNotFound => Error::<T, I>::KittyNotInList,
+			LinkedListError::ValueAlreadyExists => Error::<T, I>::KittyAlreadyInList,
+			LinkedListError::InvalidHint => Error::<T, I>::InvalidAskHint,
+			LinkedListError::HintTooFar => Error::<T, I>::AskHintTooFar,
+		}
 	}
 }
 
 decl_event!(
-	pub enum Event<T> where
+	/// Events of the pallet, a contract with indexers versioned by `EventSchemaVersion`.
+	///
+	/// Within a version, variants and their fields are never removed, changed or reordered.
+	/// New variants and fields appended to the end of a variant bump the version.
+	///
+	/// - Version 1: `Created` for both created and bred kitties.
+	/// - Version 2: `Created` is replaced by `Minted` and `Bred` with the DNA, `Ask` and `Sold`
+	///   carry the previous price.
+	pub enum Event<T, I: Instance = DefaultInstance> where
 		<T as frame_system::Trait>::AccountId,
-		<T as Trait>::KittyIndex,
-		Balance = BalanceOf<T>,
+		<T as Trait<I>>::KittyIndex,
+		<T as frame_system::Trait>::BlockNumber,
+		<T as frame_system::Trait>::Hash,
+		Balance = BalanceOf<T, I>,
 	{
-		/// A kitty is created. (owner, kitty_id)
-		Created(AccountId, KittyIndex),
+		/// A kitty is created. (owner, kitty_id, dna)
+		Minted(AccountId, KittyIndex, Dna),
+		/// A kitty is bred. (owner, kitty_id, parent_1, parent_2, dna)
+		Bred(AccountId, KittyIndex, KittyIndex, KittyIndex, Dna),
+		/// A kitty will be created with the randomness of a later block. (owner, kitty_id, reveal_at)
+		BirthRequested(AccountId, KittyIndex, BlockNumber),
 		/// A kitty is transferred. (from, to, kitty_id)
 		Transferred(AccountId, AccountId, KittyIndex),
-		/// A kitty is available for sale. (owner, kitty_id, price)
-		Ask(AccountId, KittyIndex, Option<Balance>),
-		/// A kitty is sold. (from, to, kitty_id, price)
-		Sold(AccountId, AccountId, KittyIndex, Balance),
+		/// A kitty is gifted with a message. (from, to, kitty_id, memo_hash)
+		Gifted(AccountId, AccountId, KittyIndex, Hash),
+		/// The price of a kitty for sale is set, None when delisted. (owner, kitty_id, price, previous_price)
+		Ask(AccountId, KittyIndex, Option<Balance>, Option<Balance>),
+		/// A kitty is sold. (from, to, kitty_id, price, royalty paid to the breeder out of the price,
+		/// price of the previous sale)
+		Sold(AccountId, AccountId, KittyIndex, Balance, Balance, Option<Balance>),
+		/// A kitty is burned. (owner, kitty_id)
+		Burned(AccountId, KittyIndex),
+		/// The metadata of a kitty is set. (owner, kitty_id)
+		MetadataSet(AccountId, KittyIndex),
+		/// The metadata of a kitty is cleared. (owner, kitty_id)
+		MetadataCleared(AccountId, KittyIndex),
+		/// A lease is offered. (owner, lessee, kitty_id, fee, until)
+		LeaseOffered(AccountId, AccountId, KittyIndex, Balance, BlockNumber),
+		/// A kitty is leased. (owner, lessee, kitty_id, fee, until)
+		Leased(AccountId, AccountId, KittyIndex, Balance, BlockNumber),
+		/// A lease ended and the usage rights returned to the owner. (owner, lessee, kitty_id)
+		LeaseEnded(AccountId, AccountId, KittyIndex),
 	}
 );
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
-		type Error = Error<T>;
+	pub struct Module<T: Trait<I>, I: Instance = DefaultInstance> for enum Call where origin: T::Origin {
+		type Error = Error<T, I>;
+
+		/// Version of the event schema.
+		const EventSchemaVersion: u32 = EVENT_SCHEMA_VERSION;
 
 		fn deposit_event() = default;
 
+		fn integrity_test() {
+			assert!(dna::is_valid_layout(&T::Genes::get()), "Invalid gene layout");
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			if Self::storage_version() != Releases::V1DoubleMap {
+				return 0;
+			}
+			// Lesson2 had a single collection, it moves to the default instance
+			if I::PREFIX == <DefaultInstance as Instance>::PREFIX {
+				migration::migrate_to_linked_list::<T, I>()
+			} else {
+				StorageVersion::<I>::put(Releases::V2LinkedList);
+				T::DbWeight::get().reads_writes(1, 1)
+			}
+		}
+
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			let expired = LeaseExpiries::<T, I>::take(now);
+			for kitty_id in expired.iter() {
+				if let Some(lease) = KittyLeases::<T, I>::take(kitty_id) {
+					if let Some(owner) = Self::kitty_owner(kitty_id) {
+						Self::deposit_event(RawEvent::LeaseEnded(owner, lease.lessee, *kitty_id));
+					}
+				}
+			}
+			let (due, births) = Self::finalize_due_births(now);
+
+			T::DbWeight::get().reads_writes(
+				2 + 2 * expired.len() as Weight + due as Weight + 8 * births as Weight,
+				2 + expired.len() as Weight + due as Weight + 12 * births as Weight,
+			)
+		}
+
 		/// Create a new kitty
-		#[weight = 0]
+		#[weight = T::WeightInfo::create()]
 		pub fn create(origin) {
 			let sender = ensure_signed(origin)?;
-			let kitty_id = Self::next_kitty_id()?;
-
-			// Generate a random 128bit value
-			let dna = Self::random_value(&sender);
 
-			// Create and store kitty
-			let kitty = Kitty(dna);
-			Self::insert_kitty(&sender, kitty_id, kitty);
-
-			Self::deposit_event(RawEvent::Created(sender, kitty_id));
+			with_transaction_result(|| -> DispatchResult {
+				Self::mint(&sender, None)?;
+				Ok(())
+			})?;
 		}
 
 		/// Breed kitties
-		#[weight = 0]
+		#[weight = T::WeightInfo::breed()]
 		pub fn breed(origin, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) {
 			let sender = ensure_signed(origin)?;
 
-			let new_kitty_id = Self::do_breed(&sender, kitty_id_1, kitty_id_2)?;
+			with_transaction_result(|| -> DispatchResult {
+				Self::do_breed(&sender, kitty_id_1, kitty_id_2)?;
+				Ok(())
+			})?;
+		}
+
+		/// Give its DNA to a pending kitty which is due, without waiting for `on_initialize`
+		#[weight = T::WeightInfo::claim_kitty()]
+		pub fn claim_kitty(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let birth = Self::pending_birth(kitty_id).ok_or(Error::<T, I>::NoPendingBirth)?;
+				ensure!(birth.owner == sender, Error::<T, I>::RequireOwner);
+				ensure!(birth.reveal_at <= <frame_system::Module<T>>::block_number(), Error::<T, I>::BirthNotDue);
 
-			Self::deposit_event(RawEvent::Created(sender, new_kitty_id));
+				Self::finalize_birth(kitty_id)?;
+				Ok(())
+			})?;
 		}
 
 		/// Transfer a kitty to new owner
-		#[weight = 0]
+		#[weight = T::WeightInfo::transfer()]
 		pub fn transfer(origin, to: T::AccountId, kitty_id: T::KittyIndex) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::RequireOwner);
+			with_transaction_result(|| -> DispatchResult {
+				Self::transfer_owned(&sender, &to, kitty_id)?;
+
+				Self::deposit_event(RawEvent::Transferred(sender, to, kitty_id));
+				Ok(())
+			})?;
+		}
+
+		/// Transfer several kitties to the same account, all or none of them
+		#[weight = T::WeightInfo::transfer_batch(kitty_ids.len() as u32)]
+		pub fn transfer_batch(origin, to: T::AccountId, kitty_ids: Vec<T::KittyIndex>) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				ensure!(kitty_ids.len() as u32 <= T::MaxBatchSize::get(), Error::<T, I>::BatchTooLarge);
+
+				for kitty_id in kitty_ids {
+					Self::transfer_owned(&sender, &to, kitty_id)?;
+					Self::deposit_event(RawEvent::Transferred(sender.clone(), to.clone(), kitty_id));
+				}
+				Ok(())
+			})?;
+		}
+
+		/// Transfer a kitty with the hash of a message, the message itself stays off-chain
+		#[weight = T::WeightInfo::gift()]
+		pub fn gift(origin, to: T::AccountId, kitty_id: T::KittyIndex, memo_hash: T::Hash) {
+			let sender = ensure_signed(origin)?;
 
-			Self::do_transfer(&sender, &to, kitty_id);
+			with_transaction_result(|| -> DispatchResult {
+				Self::transfer_owned(&sender, &to, kitty_id)?;
 
-			Self::deposit_event(RawEvent::Transferred(sender, to, kitty_id));
+				Self::deposit_event(RawEvent::Gifted(sender, to, kitty_id, memo_hash));
+				Ok(())
+			})?;
 		}
 
 		/// Set a price for a kitty for sale
 		/// None to delist the kitty
-		#[weight = 0]
-		pub fn ask(origin, kitty_id: T::KittyIndex, new_price: Option<BalanceOf<T>>) {
+		/// `hint` is a listed kitty with a price not higher than `new_price`, close to where
+		/// the kitty belongs in the ask book. None to search from the cheapest ask.
+		#[weight = T::WeightInfo::ask()]
+		pub fn ask(
+			origin,
+			kitty_id: T::KittyIndex,
+			new_price: Option<BalanceOf<T, I>>,
+			hint: Option<T::KittyIndex>,
+		) {
 			let sender = ensure_signed(origin)?;
 
-			ensure!(<OwnedKitties<T>>::contains_key((&sender, Some(kitty_id))), Error::<T>::RequireOwner);
+			with_transaction_result(|| -> DispatchResult {
+				Self::do_ask(&sender, kitty_id, new_price, hint)?;
+				Ok(())
+			})?;
+		}
 
-			<KittyPrices<T>>::mutate_exists(kitty_id, |price| *price = new_price);
+		/// Set or clear the price of several kitties, all or none of them
+		/// Each ask is `(kitty_id, new_price, hint)` with a hint like `ask`. The kitties are listed
+		/// from the cheapest, an ask without a hint walks from the previous kitty of the batch,
+		/// or from the cheapest ask of the book for the first one.
+		#[weight = T::WeightInfo::ask_batch(asks.len() as u32)]
+		pub fn ask_batch(
+			origin,
+			asks: Vec<BatchAskOf<T, I>>,
+		) {
+			let sender = ensure_signed(origin)?;
 
-			Self::deposit_event(RawEvent::Ask(sender, kitty_id, new_price));
+			with_transaction_result(|| -> DispatchResult {
+				ensure!(asks.len() as u32 <= T::MaxBatchSize::get(), Error::<T, I>::BatchTooLarge);
+
+				let mut kitty_ids = asks.iter().map(|(kitty_id, _, _)| *kitty_id).collect::<Vec<_>>();
+				kitty_ids.sort();
+				ensure!(kitty_ids.windows(2).all(|pair| pair[0] != pair[1]), Error::<T, I>::DuplicateKitty);
+
+				// Delisting first, None is lower than any price
+				let mut asks = asks;
+				asks.sort_by_key(|(_, price, _)| *price);
+
+				let mut previous = None;
+				for (kitty_id, new_price, hint) in asks {
+					Self::do_ask(&sender, kitty_id, new_price, hint.or(previous))?;
+					if new_price.is_some() {
+						previous = Some(kitty_id);
+					}
+				}
+				Ok(())
+			})?;
 		}
 
 		/// Buy a kitty
-		#[weight = 0]
-		pub fn buy(origin, kitty_id: T::KittyIndex, price: BalanceOf<T>) {
+		#[weight = T::WeightInfo::buy()]
+		pub fn buy(origin, kitty_id: T::KittyIndex, price: BalanceOf<T, I>) {
 			let sender = ensure_signed(origin)?;
 
-			let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidKittyId)?;
+			with_transaction_result(|| -> DispatchResult {
+				let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T, I>::InvalidKittyId)?;
 
-			let kitty_price = Self::kitty_price(kitty_id).ok_or(Error::<T>::NotForSale)?;
+				let kitty_price = Self::kitty_price(kitty_id).ok_or(Error::<T, I>::NotForSale)?;
 
-			ensure!(price >= kitty_price, Error::<T>::PriceTooLow);
+				ensure!(price >= kitty_price, Error::<T, I>::PriceTooLow);
+				ensure!(!KittyLeases::<T, I>::contains_key(kitty_id), Error::<T, I>::KittyLeased);
 
-			T::Currency::transfer(&sender, &owner, kitty_price, ExistenceRequirement::KeepAlive)?;
+				let royalty = Self::pay_sale(&sender, &owner, kitty_id, kitty_price)?;
+				let previous_sale_price = LastSalePrices::<T, I>::mutate(kitty_id, |price| price.replace(kitty_price));
 
-			<KittyPrices<T>>::remove(kitty_id);
+				Self::delist(kitty_id);
+				<KittyPrices<T, I>>::remove(kitty_id);
 
-			Self::do_transfer(&owner, &sender, kitty_id);
+				Self::do_transfer(&owner, &sender, kitty_id)?;
 
-			Self::deposit_event(RawEvent::Sold(owner, sender, kitty_id, kitty_price));
+				Self::deposit_event(RawEvent::Sold(owner, sender, kitty_id, kitty_price, royalty, previous_sale_price));
+				Ok(())
+			})?;
 		}
-	}
-}
 
-fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
-	(selector & dna1) | (!selector & dna2)
+		/// Burn a kitty, removing it from all storages
+		#[weight = T::WeightInfo::burn(T::MaxChildren::get())]
+		pub fn burn(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T, I>::RequireOwner);
+			ensure!(!KittyLeases::<T, I>::contains_key(kitty_id), Error::<T, I>::KittyLeased);
+
+			with_transaction_result(|| -> DispatchResult {
+				Self::remove_kitty(&sender, kitty_id)?;
+				T::OnKittyBurned::on_kitty_burned(kitty_id);
+
+				Self::deposit_event(RawEvent::Burned(sender, kitty_id));
+				Ok(())
+			})?;
+		}
+
+		/// Name a kitty and attach a URI, reserving a deposit for each byte
+		/// The deposit of the previous metadata is refunded
+		#[weight = T::WeightInfo::set_metadata()]
+		pub fn set_metadata(origin, kitty_id: T::KittyIndex, name: Vec<u8>, uri: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T, I>::RequireOwner);
+				ensure!(name.len() <= T::MaxNameLength::get() as usize, Error::<T, I>::NameTooLong);
+				ensure!(uri.len() <= T::MaxUriLength::get() as usize, Error::<T, I>::UriTooLong);
+
+				Self::remove_metadata(&sender, kitty_id);
+
+				let bytes = (name.len() + uri.len()) as u32;
+				let deposit = T::MetadataDepositPerByte::get() * bytes.into();
+				T::Currency::reserve(&sender, deposit)?;
+
+				Metadata::<T, I>::insert(kitty_id, KittyMetadata { name, uri, deposit });
+
+				Self::deposit_event(RawEvent::MetadataSet(sender, kitty_id));
+				Ok(())
+			})?;
+		}
+
+		/// Clear the metadata of a kitty, refunding its deposit
+		#[weight = T::WeightInfo::clear_metadata()]
+		pub fn clear_metadata(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T, I>::RequireOwner);
+
+			Self::remove_metadata(&sender, kitty_id);
+
+			Self::deposit_event(RawEvent::MetadataCleared(sender, kitty_id));
+		}
+
+		/// Offer to lend a kitty to `to` until block `until_block` for `fee`
+		/// The lease starts when `to` accepts it, replaces any previous offer
+		#[weight = T::WeightInfo::lease()]
+		pub fn lease(
+			origin,
+			kitty_id: T::KittyIndex,
+			to: T::AccountId,
+			fee: BalanceOf<T, I>,
+			until_block: T::BlockNumber,
+		) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T, I>::RequireOwner);
+			ensure!(!KittyLeases::<T, I>::contains_key(kitty_id), Error::<T, I>::KittyLeased);
+			ensure!(to != sender, Error::<T, I>::InvalidLessee);
+			ensure!(until_block > <frame_system::Module<T>>::block_number(), Error::<T, I>::LeaseExpired);
+
+			LeaseOffers::<T, I>::insert(kitty_id, Lease { lessee: to.clone(), fee, until: until_block });
+
+			Self::deposit_event(RawEvent::LeaseOffered(sender, to, kitty_id, fee, until_block));
+		}
+
+		/// Accept a lease offer, paying the fee to the owner
+		/// The kitty is removed from sale until the lease ends
+		#[weight = T::WeightInfo::accept_lease()]
+		pub fn accept_lease(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+
+			with_transaction_result(|| -> DispatchResult {
+				let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T, I>::InvalidKittyId)?;
+				let lease = Self::lease_offer(kitty_id).ok_or(Error::<T, I>::NoLeaseOffer)?;
+				ensure!(lease.lessee == sender, Error::<T, I>::InvalidLessee);
+				ensure!(lease.until > <frame_system::Module<T>>::block_number(), Error::<T, I>::LeaseExpired);
+
+				LeaseExpiries::<T, I>::try_mutate(lease.until, |expiries| {
+					ensure!((expiries.len() as u32) < T::MaxLeaseExpiries::get(), Error::<T, I>::TooManyLeaseExpiries);
+					expiries.push(kitty_id);
+					Ok::<_, Error<T, I>>(())
+				})?;
+
+				T::Currency::transfer(&sender, &owner, lease.fee, ExistenceRequirement::KeepAlive)?;
+
+				Self::delist(kitty_id);
+				<KittyPrices<T, I>>::remove(kitty_id);
+				LeaseOffers::<T, I>::remove(kitty_id);
+				KittyLeases::<T, I>::insert(kitty_id, lease.clone());
+
+				Self::deposit_event(RawEvent::Leased(owner, sender, kitty_id, lease.fee, lease.until));
+				Ok(())
+			})?;
+		}
+	}
 }
 
-impl<T: Trait> Module<T> {
+impl<T: Trait<I>, I: Instance> Module<T, I> {
 	fn random_value(sender: &T::AccountId) -> [u8; 16] {
 		let payload = (
 			T::Randomness::random_seed(),
@@ -172,53 +712,400 @@ impl<T: Trait> Module<T> {
 	fn next_kitty_id() -> sp_std::result::Result<T::KittyIndex, DispatchError> {
 		let kitty_id = Self::kitties_count();
 		if kitty_id == T::KittyIndex::max_value() {
-			return Err(Error::<T>::KittiesCountOverflow.into());
+			return Err(Error::<T, I>::KittiesCountOverflow.into());
+		}
+		Ok(kitty_id)
+	}
+
+	fn allocate_kitty_id() -> sp_std::result::Result<T::KittyIndex, DispatchError> {
+		let kitty_id = Self::next_kitty_id()?;
+		KittiesCount::<T, I>::put(kitty_id + 1u32.into());
+		Ok(kitty_id)
+	}
+
+	/// Create or breed a kitty for `owner`, now or from the randomness of a later block.
+	fn mint(
+		owner: &T::AccountId,
+		parents: Option<BreedingPair<T::KittyIndex>>,
+	) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
+		Self::note_mint(owner)?;
+		let kitty_id = Self::allocate_kitty_id()?;
+
+		let deposit = T::KittyDeposit::get();
+		T::Currency::reserve(owner, deposit)?;
+
+		let delay = T::BirthDelay::get();
+		if delay.is_zero() {
+			let dna = Self::generate_dna(parents.as_ref(), Self::random_value(owner));
+			Self::give_birth(owner, kitty_id, dna, deposit, parents)?;
+		} else {
+			// The DNA comes from randomness nobody knows yet when the kitty is requested
+			let reveal_at = <frame_system::Module<T>>::block_number() + delay;
+			PendingBirths::<T, I>::insert(kitty_id, PendingBirth {
+				owner: owner.clone(), parents, deposit, reveal_at, seed: None,
+			});
+			BirthQueue::<T, I>::append(reveal_at, kitty_id);
+			PendingBirthCount::<T, I>::mutate(owner, |count| *count += 1);
+
+			Self::deposit_event(RawEvent::BirthRequested(owner.clone(), kitty_id, reveal_at));
 		}
+
 		Ok(kitty_id)
 	}
 
-	fn insert_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
-		<OwnedKittiesList<T>>::append(owner, kitty_id);
-		<KittyOwners<T>>::insert(kitty_id, owner);
+	fn generate_dna(parents: Option<&BreedingPair<T::KittyIndex>>, seed: [u8; 16]) -> [u8; 16] {
+		match parents {
+			Some(parents) => T::BreedingStrategy::breed(&parents.dna_1, &parents.dna_2, &seed, &T::Genes::get()),
+			None => seed,
+		}
+	}
+
+	fn give_birth(
+		owner: &T::AccountId,
+		kitty_id: T::KittyIndex,
+		dna: [u8; 16],
+		deposit: BalanceOf<T, I>,
+		parents: Option<BreedingPair<T::KittyIndex>>,
+	) -> DispatchResult {
+		Self::insert_kitty(owner, kitty_id, Kitty(dna), deposit)?;
+
+		match parents {
+			Some(parents) => {
+				KittyParents::<T, I>::insert(kitty_id, (parents.kitty_id_1, parents.kitty_id_2));
+				for parent in &[parents.kitty_id_1, parents.kitty_id_2] {
+					// A parent burned while the birth was pending has no children index anymore
+					if Kitties::<T, I>::contains_key(parent) {
+						KittyChildren::<T, I>::insert(parent, kitty_id, ());
+					}
+				}
+
+				Self::deposit_event(RawEvent::Bred(
+					owner.clone(), kitty_id, parents.kitty_id_1, parents.kitty_id_2, dna,
+				));
+			},
+			None => Self::deposit_event(RawEvent::Minted(owner.clone(), kitty_id, dna)),
+		}
+
+		Ok(())
+	}
+
+	/// Give its DNA to a pending kitty from the randomness of its `reveal_at` block.
+	fn finalize_birth(kitty_id: T::KittyIndex) -> DispatchResult {
+		let birth = PendingBirths::<T, I>::take(kitty_id).ok_or(Error::<T, I>::NoPendingBirth)?;
+		let random = birth.seed.ok_or(Error::<T, I>::BirthNotDue)?;
+		PendingBirthCount::<T, I>::mutate_exists(&birth.owner, |count| {
+			*count = count.map(|count| count.saturating_sub(1)).filter(|count| *count > 0);
+		});
+
+		let seed = (
+			random,
+			&birth.owner,
+			kitty_id,
+		).using_encoded(blake2_128);
+		let dna = Self::generate_dna(birth.parents.as_ref(), seed);
+
+		Self::give_birth(&birth.owner, kitty_id, dna, birth.deposit, birth.parents)
+	}
+
+	/// Finalize the births due at `now`, at most `MaxBirthsPerBlock`.
+	/// Returns the number of due and of finalized births.
+	fn finalize_due_births(now: T::BlockNumber) -> (usize, usize) {
+		let mut due = BirthQueue::<T, I>::take(now);
+		// Births revealed in this block keep its randomness, even when finalized later
+		let random = T::Randomness::random(b"kitty birth");
+		for kitty_id in due.iter() {
+			PendingBirths::<T, I>::mutate(kitty_id, |birth| {
+				if let Some(birth) = birth.as_mut().filter(|birth| birth.seed.is_none()) {
+					birth.seed = Some(random);
+				}
+			});
+		}
+		let max = T::MaxBirthsPerBlock::get() as usize;
+		let due_count = due.len();
+		if due.len() > max {
+			let mut later = due.split_off(max);
+			later.extend(BirthQueue::<T, I>::take(now + 1u32.into()));
+			BirthQueue::<T, I>::insert(now + 1u32.into(), later);
+		}
+
+		// Kitties claimed already are not pending anymore, and a failed birth stays pending
+		// with its deposit until its owner claims it
+		let births = due.into_iter()
+			.filter(|kitty_id| with_transaction_result(|| Self::finalize_birth(*kitty_id)).is_ok())
+			.count();
+		(due_count, births)
+	}
+
+	/// Check the minting limits for a new kitty of `owner` and count it in the block.
+	fn note_mint(owner: &T::AccountId) -> DispatchResult {
+		// The list head keeps the count, no need to walk the list
+		ensure!(
+			<OwnedKittiesList<T, I>>::len(owner) + Self::pending_birth_count(owner) < T::MaxKittiesPerAccount::get(),
+			Error::<T, I>::TooManyOwnedKitties
+		);
+
+		let now = <frame_system::Module<T>>::block_number();
+		let (block, mints) = Self::block_mints();
+		let mints = if block == now { mints } else { 0 };
+		ensure!(mints < T::MaxMintsPerBlock::get(), Error::<T, I>::TooManyMintsInBlock);
+		BlockMints::<T, I>::put((now, mints + 1));
+
+		Ok(())
+	}
+
+	fn insert_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+		<OwnedKittiesList<T, I>>::append(owner, kitty_id).map_err(Error::<T, I>::from)?;
+		<KittyOwners<T, I>>::insert(kitty_id, owner);
+		Ok(())
 	}
 
-	fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: Kitty) {
+	fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: Kitty, deposit: BalanceOf<T, I>) -> DispatchResult {
+		Self::insert_owned_kitty(owner, kitty_id)?;
+
+		for (kind, variant) in KittyTraits::decode_dna(&kitty.0, &T::Genes::get()).variants().iter().enumerate() {
+			TraitCounts::<T, I>::mutate(kind as u8, variant, |count| *count += 1u32.into());
+		}
+
 		// Create and store kitty
-		Kitties::<T>::insert(kitty_id, kitty.clone());
-		KittiesCount::<T>::put(kitty_id + 1u32.into());
-		Self::insert_owned_kitty(owner, kitty_id);
+		Kitties::<T, I>::insert(kitty_id, kitty.clone());
+		KittyDeposits::<T, I>::insert(kitty_id, deposit);
+		KittyBreeders::<T, I>::insert(kitty_id, owner);
+		LiveKittiesCount::<T, I>::mutate(|count| *count += 1u32.into());
+
+		Ok(())
+	}
+
+	fn remove_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+		<OwnedKittiesList<T, I>>::remove(owner, kitty_id).map_err(Error::<T, I>::from)?;
+		<KittyOwners<T, I>>::remove(kitty_id);
+		Self::delist(kitty_id);
+		<KittyPrices<T, I>>::remove(kitty_id);
+		LeaseOffers::<T, I>::remove(kitty_id);
+		KittyBreeders::<T, I>::remove(kitty_id);
+		LastSalePrices::<T, I>::remove(kitty_id);
+		// Children of a burned kitty keep their parents, only the index of its children goes
+		if let Some((parent1, parent2)) = KittyParents::<T, I>::take(kitty_id) {
+			KittyChildren::<T, I>::remove(parent1, kitty_id);
+			KittyChildren::<T, I>::remove(parent2, kitty_id);
+		}
+		// Bounded by `MaxChildren`, only kitties migrated from lesson2 may have more
+		KittyChildren::<T, I>::remove_prefix(kitty_id);
+		BreedCounts::<T, I>::remove(kitty_id);
+		if let Some(kitty) = Kitties::<T, I>::take(kitty_id) {
+			for (kind, variant) in KittyTraits::decode_dna(&kitty.0, &T::Genes::get()).variants().iter().enumerate() {
+				TraitCounts::<T, I>::mutate(kind as u8, variant, |count| *count = count.saturating_sub(1u32.into()));
+			}
+		}
+		T::Currency::unreserve(owner, KittyDeposits::<T, I>::take(kitty_id));
+		Self::remove_metadata(owner, kitty_id);
+		// KittiesCount keeps allocating new ids, only the live count goes down
+		LiveKittiesCount::<T, I>::mutate(|count| *count = count.saturating_sub(1u32.into()));
+
+		Ok(())
+	}
+
+	/// Remove the metadata of a kitty, refunding its deposit to `owner`.
+	fn remove_metadata(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+		if let Some(metadata) = Metadata::<T, I>::take(kitty_id) {
+			T::Currency::unreserve(owner, metadata.deposit);
+		}
+	}
+
+	/// Owner, price, DNA and metadata of a kitty.
+	pub fn kitty_info(kitty_id: T::KittyIndex) -> Option<KittyInfo<T::AccountId, BalanceOf<T, I>>> {
+		let owner = Self::kitty_owner(kitty_id)?;
+		let dna = Self::decode_dna(kitty_id)?;
+		let metadata = Self::kitty_metadata(kitty_id).unwrap_or_default();
+
+		Some(KittyInfo {
+			owner,
+			price: Self::kitty_price(kitty_id),
+			dna,
+			name: metadata.name,
+			uri: metadata.uri,
+		})
+	}
+
+	/// Remove a kitty from the ask book if it is listed.
+	fn delist(kitty_id: T::KittyIndex) {
+		// Kitties not for sale are not in the book, nothing to remove
+		let _ = <AskBookList<T, I>>::remove(&(), kitty_id);
+	}
+
+	/// The cheapest kitties for sale with their price, at most `MAX_CHEAPEST_ASKS`.
+	pub fn cheapest_asks(count: u32) -> Vec<(T::KittyIndex, BalanceOf<T, I>)> {
+		<AskBookList<T, I>>::iter(&())
+			.take(count.min(MAX_CHEAPEST_ASKS) as usize)
+			.map(|kitty_id| (kitty_id, KittyPriceOf::<T, I>::convert(kitty_id)))
+			.collect()
+	}
+
+	/// Kitties of an account in insertion order, at most `MAX_OWNED_KITTIES_PAGE` per page.
+	pub fn owned_kitties_page(
+		owner: &T::AccountId,
+		start_after: Option<T::KittyIndex>,
+		count: u32,
+	) -> Vec<T::KittyIndex> {
+		<OwnedKittiesList<T, I>>::page(owner, start_after, count.min(MAX_OWNED_KITTIES_PAGE))
+	}
+
+	/// Decode the traits of a kitty and score its rarity against the live kitties.
+	pub fn decode_dna(kitty_id: T::KittyIndex) -> Option<DecodedDna> {
+		let kitty = Self::kitties(kitty_id)?;
+		let traits = KittyTraits::decode_dna(&kitty.0, &T::Genes::get());
+		let live: u64 = Self::live_kitties_count().saturated_into();
+
+		// A trait shared by fewer kitties scores higher
+		let rarity_score = traits.variants().iter().enumerate()
+			.map(|(kind, variant)| {
+				let count: u64 = Self::trait_count(kind as u8, variant).saturated_into();
+				live.saturating_mul(dna::RARITY_SCALE) / count.max(1)
+			})
+			.sum();
+
+		Some(DecodedDna {
+			dna: kitty.0,
+			traits,
+			rarity_score,
+		})
 	}
 
 	fn do_breed(sender: &T::AccountId, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
-		let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyId)?;
-		let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyId)?;
+		let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T, I>::InvalidKittyId)?;
+		let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T, I>::InvalidKittyId)?;
+
+		ensure!(Self::can_use(sender, kitty_id_1), Error::<T, I>::RequireOwner);
+		ensure!(Self::can_use(sender, kitty_id_2), Error::<T, I>::RequireOwner);
+		ensure!(kitty_id_1 != kitty_id_2, Error::<T, I>::RequireDifferentParent);
+		let max_children = T::MaxChildren::get();
+		ensure!(Self::breed_count(kitty_id_1) < max_children, Error::<T, I>::TooManyChildren);
+		ensure!(Self::breed_count(kitty_id_2) < max_children, Error::<T, I>::TooManyChildren);
+
+		let kitty_id = Self::mint(sender, Some(BreedingPair {
+			kitty_id_1,
+			kitty_id_2,
+			dna_1: kitty1.0,
+			dna_2: kitty2.0,
+		}))?;
+		BreedCounts::<T, I>::mutate(kitty_id_1, |count| *count += 1);
+		BreedCounts::<T, I>::mutate(kitty_id_2, |count| *count += 1);
 
-		ensure!(<OwnedKitties<T>>::contains_key((&sender, Some(kitty_id_1))), Error::<T>::RequireOwner);
-		ensure!(<OwnedKitties<T>>::contains_key((&sender, Some(kitty_id_2))), Error::<T>::RequireOwner);
-		ensure!(kitty_id_1 != kitty_id_2, Error::<T>::RequireDifferentParent);
+		Ok(kitty_id)
+	}
 
-		let kitty_id = Self::next_kitty_id()?;
+	/// Pay `price` of a sold kitty from `buyer` to `seller`, routing the royalty to the breeder.
+	/// Every sale of a kitty goes through here, returns the royalty paid.
+	pub fn pay_sale(
+		buyer: &T::AccountId,
+		seller: &T::AccountId,
+		kitty_id: T::KittyIndex,
+		price: BalanceOf<T, I>,
+	) -> sp_std::result::Result<BalanceOf<T, I>, DispatchError> {
+		// No royalty when the breeder sells
+		let royalty = match Self::kitty_breeder(kitty_id) {
+			Some(breeder) if breeder != *seller => {
+				let royalty = T::BreederRoyalty::get() * price;
+				// A royalty too small to recreate a reaped breeder account stays with the seller
+				if T::Currency::total_balance(&breeder).is_zero() && royalty < T::Currency::minimum_balance() {
+					Zero::zero()
+				} else {
+					T::Currency::transfer(buyer, &breeder, royalty, ExistenceRequirement::KeepAlive)?;
+					royalty
+				}
+			}
+			_ => Zero::zero(),
+		};
+
+		T::Currency::transfer(buyer, seller, price - royalty, ExistenceRequirement::KeepAlive)?;
+
+		Ok(royalty)
+	}
+
+	/// Whether an account may use a kitty, the lessee of a leased kitty uses it instead of the owner.
+	pub fn can_use(sender: &T::AccountId, kitty_id: T::KittyIndex) -> bool {
+		match Self::kitty_lease(kitty_id) {
+			Some(lease) => lease.lessee == *sender,
+			None => <OwnedKitties<T, I>>::contains_key((sender, Some(kitty_id))),
+		}
+	}
 
-		let kitty1_dna = kitty1.0;
-		let kitty2_dna = kitty2.0;
+	/// Transfer a kitty of `from` that is not leased.
+	pub fn transfer_owned(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+		ensure!(Self::kitty_owner(kitty_id).as_ref() == Some(from), Error::<T, I>::RequireOwner);
+		ensure!(!KittyLeases::<T, I>::contains_key(kitty_id), Error::<T, I>::KittyLeased);
 
-		// Generate a random 128bit value
-		let selector = Self::random_value(&sender);
-		let mut new_dna = [0u8; 16];
+		Self::do_transfer(from, to, kitty_id)
+	}
+
+	/// Move a kitty of `owner` that is not leased to `custodian`, taking it off the market.
+	/// Used by pallets holding kitties on behalf of their owners, the custodian can not sign.
+	pub fn take_custody(owner: &T::AccountId, custodian: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+		Self::transfer_owned(owner, custodian, kitty_id)?;
+		Self::delist(kitty_id);
+		<KittyPrices<T, I>>::remove(kitty_id);
+		Ok(())
+	}
+
+	fn do_ask(
+		owner: &T::AccountId,
+		kitty_id: T::KittyIndex,
+		new_price: Option<BalanceOf<T, I>>,
+		hint: Option<T::KittyIndex>,
+	) -> DispatchResult {
+		ensure!(<OwnedKitties<T, I>>::contains_key((owner, Some(kitty_id))), Error::<T, I>::RequireOwner);
+		ensure!(!KittyLeases::<T, I>::contains_key(kitty_id), Error::<T, I>::KittyLeased);
 
-		// Combine parents and selector to create new kitty
-		for i in 0..kitty1_dna.len() {
-			new_dna[i] = combine_dna(kitty1_dna[i], kitty2_dna[i], selector[i]);
+		Self::delist(kitty_id);
+
+		let previous_price = <KittyPrices<T, I>>::mutate_exists(kitty_id, |price| {
+			sp_std::mem::replace(price, new_price)
+		});
+
+		if new_price.is_some() {
+			<AskBookList<T, I>>::insert_with_hint(&(), kitty_id, hint, T::MaxAskBookWalk::get())
+				.map_err(Error::<T, I>::from)?;
 		}
 
-		Self::insert_kitty(sender, kitty_id, Kitty(new_dna));
+		Self::deposit_event(RawEvent::Ask(owner.clone(), kitty_id, new_price, previous_price));
 
-		Ok(kitty_id)
+		Ok(())
 	}
 
-	fn do_transfer(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex)  {
-		<OwnedKittiesList<T>>::remove(&from, kitty_id);
-		Self::insert_owned_kitty(&to, kitty_id);
+	fn do_transfer(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+		if from == to {
+			return Ok(());
+		}
+
+		<OwnedKittiesList<T, I>>::move_to(from, to, kitty_id).map_err(Error::<T, I>::from)?;
+		<KittyOwners<T, I>>::insert(kitty_id, to);
+		// An offer is made by the owner, it does not follow the kitty
+		LeaseOffers::<T, I>::remove(kitty_id);
+
+		// The deposits stay reserved, they are only moved to the new owner with the metadata
+		let metadata_deposit = Self::kitty_metadata(kitty_id)
+			.map(|metadata| metadata.deposit)
+			.unwrap_or_else(Zero::zero);
+		let deposit = Self::kitty_deposit(kitty_id) + metadata_deposit;
+		Self::move_deposit(from, to, deposit)
+	}
+
+	/// Move a `deposit` reserved by `from` to the reserved balance of `to`.
+	fn move_deposit(from: &T::AccountId, to: &T::AccountId, deposit: BalanceOf<T, I>) -> DispatchResult {
+		if deposit.is_zero() {
+			return Ok(());
+		}
+
+		if T::Currency::total_balance(to).is_zero() {
+			// Reserved funds can not be repatriated to an account that does not exist,
+			// the deposit is transferred to create it and reserved again
+			ensure!(T::Currency::unreserve(from, deposit).is_zero(), Error::<T, I>::DepositNotReserved);
+			T::Currency::transfer(from, to, deposit, ExistenceRequirement::AllowDeath)?;
+			T::Currency::reserve(to, deposit)
+		} else {
+			let remainder = T::Currency::repatriate_reserved(from, to, deposit, BalanceStatus::Reserved)?;
+			ensure!(remainder.is_zero(), Error::<T, I>::DepositNotReserved);
+			Ok(())
+		}
 	}
 }
 
@@ -226,11 +1113,10 @@ impl<T: Trait> Module<T> {
 #[cfg(test)]
 mod tests {
 	use super::*;
-
 	use sp_core::H256;
-	use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+	use frame_support::{impl_outer_origin, impl_outer_event, parameter_types, weights::Weight, assert_ok, assert_noop};
 	use sp_runtime::{
-		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill, Permill,
 	};
 	use frame_system as system;
 	use pallet_balances as balances;
@@ -239,6 +1125,19 @@ mod tests {
 		pub enum Origin for Test {}
 	}
 
+	mod kitties {
+		pub use crate::{Event, Instance1};
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system<T>,
+			balances<T>,
+			kitties<T>,
+			kitties Instance1<T>,
+		}
+	}
+
 	// For testing the module, we construct most of a mock runtime. This means
 	// first constructing a configuration type (`Test`) which `impl`s each of the
 	// configuration traits of modules we want to use.
@@ -250,7 +1149,52 @@ mod tests {
 		pub const MaximumBlockLength: u32 = 2 * 1024;
 		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
 	
-		pub const ExistentialDeposit: u64 = 1;
+		pub const KittyDeposit: u64 = 100;
+		pub const NoMutation: Permill = Permill::zero();
+		pub const AlwaysMutation: Permill = Permill::one();
+		pub const MaxAskBookWalk: u32 = 2;
+		pub const MaxLeaseExpiries: u32 = 2;
+		pub const BreederRoyalty: Permill = Permill::from_percent(10);
+		pub const MaxKittiesPerAccount: u32 = 5;
+		pub const MaxMintsPerBlock: u32 = 8;
+		pub const MetadataDepositPerByte: u64 = 2;
+		pub const MaxNameLength: u32 = 8;
+		pub const MaxUriLength: u32 = 32;
+		pub const MaxBirthsPerBlock: u32 = 2;
+		pub const MaxBatchSize: u32 = 3;
+		pub const MaxChildren: u32 = 3;
+
+		pub const DogDeposit: u64 = 50;
+		pub const DogGenes: Genes = [(4, 3), (3, 5), (2, 2), (1, 4), (0, 6)];
+	}
+
+	thread_local! {
+		static BIRTH_DELAY: std::cell::RefCell<u64> = const { std::cell::RefCell::new(0) };
+		static EXISTENTIAL_DEPOSIT: std::cell::RefCell<u64> = const { std::cell::RefCell::new(1) };
+	}
+
+	/// Kitties are born instantly unless a test sets a delay.
+	pub struct BirthDelay;
+	impl Get<u64> for BirthDelay {
+		fn get() -> u64 {
+			BIRTH_DELAY.with(|delay| *delay.borrow())
+		}
+	}
+
+	fn set_birth_delay(delay: u64) {
+		BIRTH_DELAY.with(|value| *value.borrow_mut() = delay);
+	}
+
+	/// One unit unless a test raises it.
+	pub struct ExistentialDeposit;
+	impl Get<u64> for ExistentialDeposit {
+		fn get() -> u64 {
+			EXISTENTIAL_DEPOSIT.with(|deposit| *deposit.borrow())
+		}
+	}
+
+	fn set_existential_deposit(deposit: u64) {
+		EXISTENTIAL_DEPOSIT.with(|value| *value.borrow_mut() = deposit);
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -262,7 +1206,7 @@ mod tests {
 		type AccountId = u64;
 		type Lookup = IdentityLookup<Self::AccountId>;
 		type Header = Header;
-		type Event = ();
+		type Event = TestEvent;
 		type BlockHashCount = BlockHashCount;
 		type MaximumBlockWeight = MaximumBlockWeight;
 		type DbWeight = ();
@@ -283,7 +1227,7 @@ mod tests {
 	impl balances::Trait for Test {
 		type Balance = u64;
 		type MaxLocks = ();
-		type Event = ();
+		type Event = TestEvent;
 		type DustRemoval = ();
 		type ExistentialDeposit = ExistentialDeposit;
 		type AccountStore = system::Module<Test>;
@@ -295,72 +1239,137 @@ mod tests {
 
 	impl Trait for Test {
 		type KittyIndex = u32;
-		type Event = ();
+		type Event = TestEvent;
+		type Currency = Balances;
+		type Randomness = RandomnessCollectiveFlip;
+		type KittyDeposit = KittyDeposit;
+		type BreedingStrategy = SelectorBreeding;
+		type MaxAskBookWalk = MaxAskBookWalk;
+		type MaxLeaseExpiries = MaxLeaseExpiries;
+		type BreederRoyalty = BreederRoyalty;
+		type MaxKittiesPerAccount = MaxKittiesPerAccount;
+		type MaxMintsPerBlock = MaxMintsPerBlock;
+		type MetadataDepositPerByte = MetadataDepositPerByte;
+		type MaxNameLength = MaxNameLength;
+		type MaxUriLength = MaxUriLength;
+		type Genes = KittyGenes;
+		type BirthDelay = BirthDelay;
+		type MaxBirthsPerBlock = MaxBirthsPerBlock;
+		type MaxBatchSize = MaxBatchSize;
+		type MaxChildren = MaxChildren;
+		type OnKittyBurned = ();
+		type WeightInfo = ();
+	}
+
+	/// A second collection with its own storage, deposit and gene layout.
+	impl Trait<Instance1> for Test {
+		type KittyIndex = u64;
+		type Event = TestEvent;
 		type Currency = Balances;
 		type Randomness = RandomnessCollectiveFlip;
+		type KittyDeposit = DogDeposit;
+		type BreedingStrategy = SelectorBreeding;
+		type MaxAskBookWalk = MaxAskBookWalk;
+		type MaxLeaseExpiries = MaxLeaseExpiries;
+		type BreederRoyalty = BreederRoyalty;
+		type MaxKittiesPerAccount = MaxKittiesPerAccount;
+		type MaxMintsPerBlock = MaxMintsPerBlock;
+		type MetadataDepositPerByte = MetadataDepositPerByte;
+		type MaxNameLength = MaxNameLength;
+		type MaxUriLength = MaxUriLength;
+		type Genes = DogGenes;
+		type BirthDelay = BirthDelay;
+		type MaxBirthsPerBlock = MaxBirthsPerBlock;
+		type MaxBatchSize = MaxBatchSize;
+		type MaxChildren = MaxChildren;
+		type OnKittyBurned = ();
+		type WeightInfo = ();
 	}
 	type OwnedKittiesTest = OwnedKitties<Test>;
 	type KittyLinkedItem = LinkedItem<<Test as Trait>::KittyIndex>;
-	type OwnedKittiesList = LinkedList<OwnedKitties<Test>, <Test as system::Trait>::AccountId, <Test as Trait>::KittyIndex>;
+	type OwnedKittiesList = LinkedList<MapLayout<OwnedKitties<Test>>, <Test as system::Trait>::AccountId, <Test as Trait>::KittyIndex>;
+	type KittiesModule = Module<Test>;
+	type DogsModule = Module<Test, Instance1>;
+	type System = system::Module<Test>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
-	fn new_test_ext() -> sp_io::TestExternalities {
-		system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1000), (2, 1000), (3, 150)],
+		}.assimilate_storage(&mut t).unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		// The randomness of block 0 is not defined
+		ext.execute_with(|| system::Module::<Test>::set_block_number(1));
+		ext
+	}
+
+	fn last_event() -> TestEvent {
+		System::events().pop().expect("An event is deposited").event
 	}
 
 	#[test]
 	fn owned_kitties_can_append_values() {
 		new_test_ext().execute_with(|| {
-			OwnedKittiesList::append(&0, 1);
+			assert_ok!(OwnedKittiesList::append(&0, 1));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: Some(1),
 				next: Some(1),
+				len: 1,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), Some(KittyLinkedItem {
 				prev: None,
 				next: None,
+				len: 0,
 			}));
 
-			OwnedKittiesList::append(&0, 2);
+			assert_ok!(OwnedKittiesList::append(&0, 2));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: Some(2),
 				next: Some(1),
+				len: 2,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), Some(KittyLinkedItem {
 				prev: None,
 				next: Some(2),
+				len: 0,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), Some(KittyLinkedItem {
 				prev: Some(1),
 				next: None,
+				len: 0,
 			}));
 
-			OwnedKittiesList::append(&0, 3);
+			assert_ok!(OwnedKittiesList::append(&0, 3));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: Some(3),
 				next: Some(1),
+				len: 3,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), Some(KittyLinkedItem {
 				prev: None,
 				next: Some(2),
+				len: 0,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), Some(KittyLinkedItem {
 				prev: Some(1),
 				next: Some(3),
+				len: 0,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(3))), Some(KittyLinkedItem {
 				prev: Some(2),
 				next: None,
+				len: 0,
 			}));
 		});
 	}
@@ -368,57 +1377,1250 @@ mod tests {
 	#[test]
 	fn owned_kitties_can_remove_values() {
 		new_test_ext().execute_with(|| {
-			OwnedKittiesList::append(&0, 1);
-			OwnedKittiesList::append(&0, 2);
-			OwnedKittiesList::append(&0, 3);
+			assert_ok!(OwnedKittiesList::append(&0, 1));
+			assert_ok!(OwnedKittiesList::append(&0, 2));
+			assert_ok!(OwnedKittiesList::append(&0, 3));
 
-			OwnedKittiesList::remove(&0, 2);
+			assert_ok!(OwnedKittiesList::remove(&0, 2));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: Some(3),
 				next: Some(1),
+				len: 2,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), Some(KittyLinkedItem {
 				prev: None,
 				next: Some(3),
+				len: 0,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), None);
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(3))), Some(KittyLinkedItem {
 				prev: Some(1),
 				next: None,
+				len: 0,
 			}));
 
-			OwnedKittiesList::remove(&0, 1);
+			assert_ok!(OwnedKittiesList::remove(&0, 1));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: Some(3),
 				next: Some(3),
+				len: 1,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), None);
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), None);
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, Some(3))), Some(KittyLinkedItem {
 				prev: None,
 				next: None,
+				len: 0,
 			}));
 
-			OwnedKittiesList::remove(&0, 3);
+			assert_ok!(OwnedKittiesList::remove(&0, 3));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem {
+			assert_eq!(OwnedKittiesTest::get((0, None::<u32>)), Some(KittyLinkedItem {
 				prev: None,
 				next: None,
+				len: 0,
+			}));
+
+			assert_eq!(OwnedKittiesTest::get((0, Some(1))), None);
+
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), None);
+
+			assert_eq!(OwnedKittiesTest::get((0, Some(2))), None);
+		});
+	}
+
+	#[test]
+	fn burn_kitty_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(10), None));
+
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+
+			assert_eq!(KittiesModule::kitties(0), None);
+			assert_eq!(KittiesModule::kitty_owner(0), None);
+			assert_eq!(KittiesModule::kitty_price(0), None);
+			assert_eq!(OwnedKittiesTest::get((1, Some(0))), None);
+			assert_eq!(OwnedKittiesTest::get((1, None::<u32>)), Some(KittyLinkedItem {
+				prev: Some(1),
+				next: Some(1),
+				len: 1,
 			}));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+			assert_eq!(KittiesModule::kitties_count(), 2);
+			assert_eq!(KittiesModule::live_kitties_count(), 1);
+
+			// burned ids are never reused
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_eq!(KittiesModule::kitty_owner(2), Some(1));
+			assert_eq!(KittiesModule::live_kitties_count(), 2);
+		});
+	}
+
+	#[test]
+	fn burn_kitty_leaves_no_index_entries() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 2));
+			assert_ok!(KittiesModule::set_metadata(Origin::signed(1), 2, b"tom".to_vec(), b"ipfs://a".to_vec()));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 2, Some(10), None));
+
+			// Kitty 2 has parents, a child, metadata and an ask
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 2));
+
+			assert_eq!(KittyChildren::<Test>::iter_prefix(2).count(), 0);
+			assert!(!KittyChildren::<Test>::contains_key(0, 2));
+			assert!(!KittyChildren::<Test>::contains_key(1, 2));
+			assert!(KittyChildren::<Test>::contains_key(0, 3));
+			assert_eq!(KittiesModule::kitty_parents(2), None);
+			assert_eq!(KittiesModule::kitty_parents(3), Some((0, 2)));
+			assert!(!Kitties::<Test>::contains_key(2));
+			assert!(!KittyOwners::<Test>::contains_key(2));
+			assert!(!KittyPrices::<Test>::contains_key(2));
+			assert!(!KittyDeposits::<Test>::contains_key(2));
+			assert!(!KittyBreeders::<Test>::contains_key(2));
+			assert!(!Metadata::<Test>::contains_key(2));
+			assert!(!BreedCounts::<Test>::contains_key(2));
+			assert_eq!(KittiesModule::breed_count(0), 2);
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![]);
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![0, 1, 3]);
+		});
+	}
+
+	#[test]
+	fn breed_kitty_failed_when_too_many_children() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			for _ in 0..3 {
+				assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			}
+			assert_eq!(KittiesModule::breed_count(1), 3);
+
+			// Burning a child does not allow breeding more
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 4));
+			assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test, DefaultInstance>::TooManyChildren);
+			assert_noop!(KittiesModule::breed(Origin::signed(1), 2, 1), Error::<Test, DefaultInstance>::TooManyChildren);
+		});
+	}
+
+	#[test]
+	fn burn_parent_of_pending_birth_leaves_no_children_index() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			set_birth_delay(2);
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+
+			system::Module::<Test>::set_block_number(3);
+			KittiesModule::on_initialize(3);
+
+			assert_eq!(KittiesModule::kitty_owner(2), Some(1));
+			assert_eq!(KittiesModule::kitty_parents(2), Some((0, 1)));
+			assert!(!KittyChildren::<Test>::contains_key(0, 2));
+			assert!(KittyChildren::<Test>::contains_key(1, 2));
+			set_birth_delay(0);
+		});
+	}
+
+	#[test]
+	fn burn_kitty_failed_when_not_owner() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_noop!(KittiesModule::burn(Origin::signed(2), 0), Error::<Test, DefaultInstance>::RequireOwner);
+			assert_noop!(KittiesModule::burn(Origin::signed(1), 1), Error::<Test, DefaultInstance>::RequireOwner);
+		});
+	}
+
+	#[test]
+	fn create_and_breed_emit_dna() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			let dna_0 = KittiesModule::kitties(0).unwrap().0;
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Minted(1, 0, dna_0)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			let dna_2 = KittiesModule::kitties(2).unwrap().0;
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Bred(1, 2, 0, 1, dna_2)));
+
+			// Each collection emits its own events
+			assert_ok!(DogsModule::create(Origin::signed(2)));
+			let dog_dna = DogsModule::kitties(0).unwrap().0;
+			assert_eq!(last_event(), TestEvent::kitties_Instance1(RawEvent::Minted(2, 0, dog_dna)));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+	#[test]
+	fn create_kitty_reserves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+			assert_eq!(Balances::reserved_balance(1), 100);
+			assert_eq!(Balances::free_balance(1), 900);
+			assert_eq!(KittiesModule::kitty_deposit(0), 100);
 		});
 	}
+
+	#[test]
+	fn create_kitty_failed_when_insufficient_balance() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				KittiesModule::create(Origin::signed(4)),
+				balances::Error::<Test, balances::DefaultInstance>::InsufficientBalance
+			);
+			assert_eq!(KittiesModule::kitties_count(), 0);
+		});
+	}
+
+	#[test]
+	fn breed_kitty_reserves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+
+			assert_eq!(Balances::reserved_balance(1), 300);
+			assert_eq!(KittiesModule::kitty_deposit(2), 100);
+		});
+	}
+
+	#[test]
+	fn breed_kitty_failed_when_insufficient_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(3)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 3, 1));
+			assert_eq!(Balances::free_balance(3), 50);
+
+			assert_noop!(
+				KittiesModule::breed(Origin::signed(3), 0, 1),
+				balances::Error::<Test, balances::DefaultInstance>::InsufficientBalance
+			);
+		});
+	}
+
+	#[test]
+	fn transfer_kitty_moves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 900);
+			assert_eq!(Balances::reserved_balance(2), 100);
+			assert_eq!(Balances::free_balance(2), 1000);
+		});
+	}
+
+	#[test]
+	fn transfer_kitty_to_unfunded_account_moves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 9, 0));
+
+			assert_eq!(KittiesModule::kitty_owner(0), Some(9));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 900);
+			assert_eq!(Balances::reserved_balance(9), 100);
+			assert_eq!(Balances::free_balance(9), 0);
+		});
+	}
+
+	#[test]
+	fn transfer_kitty_failed_when_deposit_not_reserved() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			let _ = Balances::slash_reserved(&1, 50);
+
+			assert_noop!(KittiesModule::transfer(Origin::signed(1), 2, 0), Error::<Test, DefaultInstance>::DepositNotReserved);
+			assert_noop!(KittiesModule::transfer(Origin::signed(1), 9, 0), Error::<Test, DefaultInstance>::DepositNotReserved);
+		});
+	}
+
+	#[test]
+	fn buy_kitty_moves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(200), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(2), 0, 200));
+
+			assert_eq!(KittiesModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 1100);
+			assert_eq!(Balances::reserved_balance(2), 100);
+			assert_eq!(Balances::free_balance(2), 800);
+		});
+	}
+
+	#[test]
+	fn buy_kitty_failed_when_insufficient_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(500), None));
+
+			assert_noop!(
+				KittiesModule::buy(Origin::signed(3), 0, 500),
+				balances::Error::<Test, balances::DefaultInstance>::InsufficientBalance
+			);
+			assert_eq!(KittiesModule::kitty_owner(0), Some(1));
+		});
+	}
+
+	#[test]
+	fn burn_kitty_releases_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 1100);
+			assert_eq!(KittiesModule::kitty_deposit(0), 0);
+		});
+	}
+
+	#[test]
+	fn decode_dna_works() {
+		new_test_ext().execute_with(|| {
+			let mut dna = [0u8; 16];
+			dna[..5].copy_from_slice(&[16, 2, 23, 9, 14]);
+			assert_ok!(KittiesModule::insert_kitty(&1, 0, Kitty(dna), 0));
+			assert_ok!(KittiesModule::insert_kitty(&1, 1, Kitty([5u8; 16]), 0));
+
+			assert_eq!(KittiesModule::decode_dna(0), Some(DecodedDna {
+				dna,
+				traits: KittyTraits {
+					body: 1,
+					eyes: 2,
+					accessory: 3,
+					colour: 9,
+					mouth: 4,
+				},
+				rarity_score: 1000,
+			}));
+
+			assert_ok!(KittiesModule::insert_kitty(&1, 2, Kitty(dna), 0));
+
+			assert_eq!(KittiesModule::decode_dna(0).map(|d| d.rarity_score), Some(750));
+			assert_eq!(KittiesModule::decode_dna(1).map(|d| d.rarity_score), Some(1500));
+
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 2));
+
+			assert_eq!(KittiesModule::trait_count(0, 1), 1);
+			assert_eq!(KittiesModule::decode_dna(0).map(|d| d.rarity_score), Some(1000));
+			assert_eq!(KittiesModule::decode_dna(2), None);
+		});
+	}
+
+	#[test]
+	fn selector_breeding_picks_bits_from_parents() {
+		let dna = SelectorBreeding::breed(&[0b1111_0000; 16], &[0b0011_1100; 16], &[0b1010_1010; 16], &dna::GENES);
+
+		assert_eq!(dna, [0b1011_0100; 16]);
+	}
+
+	#[test]
+	fn mendelian_breeding_is_reproducible_from_seed() {
+		let dna1 = [0x11u8; 16];
+		let dna2 = [0x92u8; 16];
+		let seed = [7u8; 16];
+
+		let child = MendelianBreeding::<NoMutation>::breed(&dna1, &dna2, &seed, &dna::GENES);
+
+		assert_eq!(child, MendelianBreeding::<NoMutation>::breed(&dna1, &dna2, &seed, &dna::GENES));
+		assert_ne!(child, MendelianBreeding::<NoMutation>::breed(&dna1, &dna2, &[8u8; 16], &dna::GENES));
+	}
+
+	#[test]
+	fn mendelian_breeding_expresses_dominant_allele() {
+		// Parent 1 only carries recessive alleles, parent 2 only dominant ones
+		let dna1 = [0x11u8; 16];
+		let dna2 = [0x92u8; 16];
+
+		for i in 0..16u8 {
+			let child = MendelianBreeding::<NoMutation>::breed(&dna1, &dna2, &[i; 16], &dna::GENES);
+
+			for &(index, _) in dna::GENES.iter() {
+				assert_eq!(child[index], 0x92);
+				assert_eq!(child[index + dna::RECESSIVE_OFFSET], 0x11);
+			}
+		}
+	}
+
+	#[test]
+	fn mendelian_breeding_mutates_alleles() {
+		let dna1 = [0x11u8; 16];
+		let dna2 = [0x92u8; 16];
+		let seed = [7u8; 16];
+
+		let child = MendelianBreeding::<AlwaysMutation>::breed(&dna1, &dna2, &seed, &dna::GENES);
+		let inherited = MendelianBreeding::<NoMutation>::breed(&dna1, &dna2, &seed, &dna::GENES);
+
+		assert!(dna::GENES.iter().any(|&(index, _)| child[index] != inherited[index]));
+	}
+
+	#[test]
+	fn invalid_gene_layout_does_not_panic() {
+		let genes = [(0, 0), (6, 10), (8, 10), (15, 10), (16, 10)];
+		assert!(!dna::is_valid_layout(&genes));
+		assert!(dna::is_valid_layout(&dna::GENES));
+
+		let dna = [0x92u8; 16];
+		let traits = KittyTraits::decode_dna(&dna, &genes);
+		assert_eq!(traits.variants(), [0, 6, 6, 6, 0]);
+
+		// Genes without room for a recessive allele are inherited like other bytes
+		let child = MendelianBreeding::<NoMutation>::breed(&[0x11u8; 16], &dna, &[0u8; 16], &genes);
+		assert_eq!((child[6], child[14]), (0x92, 0x11));
+		assert_eq!(child[15], 0x92);
+	}
+
+	#[test]
+	fn owned_kitties_page_is_bounded() {
+		new_test_ext().execute_with(|| {
+			for i in 0..MAX_OWNED_KITTIES_PAGE + 1 {
+				assert_ok!(OwnedKittiesList::append(&0, i));
+			}
+
+			assert_eq!(
+				KittiesModule::owned_kitties_page(&0, None, u32::MAX).len(),
+				MAX_OWNED_KITTIES_PAGE as usize
+			);
+			assert_eq!(
+				KittiesModule::owned_kitties_page(&0, Some(MAX_OWNED_KITTIES_PAGE - 1), 10),
+				vec![MAX_OWNED_KITTIES_PAGE]
+			);
+		});
+	}
+
+	#[test]
+	fn ask_book_is_sorted_by_price() {
+		new_test_ext().execute_with(|| {
+			for _ in 0..4 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(30), None));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 1, Some(10), None));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 2, Some(20), Some(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 3, Some(40), Some(0)));
+
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(1, 10), (2, 20), (0, 30), (3, 40)]);
+			assert_eq!(KittiesModule::cheapest_asks(2), vec![(1, 10), (2, 20)]);
+
+			// Changing the price moves the ask
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 1, Some(35), Some(0)));
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(2, 20), (0, 30), (1, 35), (3, 40)]);
+
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, None, None));
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(2, 20), (1, 35), (3, 40)]);
+		});
+	}
+
+	#[test]
+	fn ask_book_removes_sold_and_burned_kitties() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 1, Some(20), None));
+
+			assert_ok!(KittiesModule::buy(Origin::signed(2), 0, 10));
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(1, 20)]);
+
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 1));
+			assert_eq!(KittiesModule::cheapest_asks(10), Vec::<(u32, u64)>::new());
+		});
+	}
+
+	#[test]
+	fn ask_failed_when_hint_is_invalid() {
+		new_test_ext().execute_with(|| {
+			for _ in 0..5 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			for kitty_id in 0..4u32 {
+				let hint = kitty_id.checked_sub(1);
+				assert_ok!(KittiesModule::ask(Origin::signed(1), kitty_id, Some(10 * (kitty_id as u64 + 1)), hint));
+			}
+
+			// Hint priced higher than the ask
+			assert_noop!(
+				KittiesModule::ask(Origin::signed(1), 4, Some(15), Some(2)),
+				Error::<Test, DefaultInstance>::InvalidAskHint
+			);
+			// Hint not in the book
+			assert_noop!(
+				KittiesModule::ask(Origin::signed(1), 4, Some(15), Some(4)),
+				Error::<Test, DefaultInstance>::InvalidAskHint
+			);
+			// More than MaxAskBookWalk asks between the hint and the position
+			assert_noop!(
+				KittiesModule::ask(Origin::signed(1), 4, Some(45), None),
+				Error::<Test, DefaultInstance>::AskHintTooFar
+			);
+
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 4, Some(45), Some(1)));
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(0, 10), (1, 20), (2, 30), (3, 40), (4, 45)]);
+		});
+	}
+
+	#[test]
+	fn migration_rebuilds_owned_kitties_from_double_map() {
+		use frame_support::{Blake2_128Concat, StorageHasher, traits::OnRuntimeUpgrade};
+		use frame_support::storage::migration::{put_storage_value, get_storage_value};
+
+		let hash = |key: &[u8]| Blake2_128Concat::hash(key);
+		let hash2 = |key1: &[u8], key2: &[u8]| [hash(key1), hash(key2)].concat();
+
+		new_test_ext().execute_with(|| {
+			// Lesson2 storage: kitties 0 and 1 created, kitty 2 bred from them, 0 sold to account 2
+			let old = migration::OLD_MODULE;
+			for (kitty_id, owner, dna) in &[(0u32, 2u64, [1u8; 16]), (1, 1, [2u8; 16]), (2, 1, [3u8; 16])] {
+				put_storage_value(old, b"Kitties", &hash(&kitty_id.encode()), Kitty(*dna));
+				put_storage_value(old, b"KittyOwners", &hash(&kitty_id.encode()), *owner);
+				put_storage_value(old, b"OwnedKitties", &hash2(&owner.encode(), &kitty_id.encode()), *kitty_id);
+			}
+			put_storage_value(old, b"KittiesCount", &[], 3u32);
+			put_storage_value(old, b"KittyParents", &hash(&0u32.encode()), (0u32, 0u32));
+			put_storage_value(old, b"KittyParents", &hash(&1u32.encode()), (0u32, 0u32));
+			put_storage_value(old, b"KittyParents", &hash(&2u32.encode()), (0u32, 1u32));
+			put_storage_value(old, b"KittyChildren", &hash2(&0u32.encode(), &2u32.encode()), 2u32);
+			put_storage_value(old, b"KittyChildren", &hash2(&1u32.encode(), &2u32.encode()), 2u32);
+			put_storage_value(old, b"KittySpouse", &hash2(&0u32.encode(), &1u32.encode()), 1u32);
+			// Account 1 reserved the deposits of the three kitties, the sale of kitty 0 released one.
+			// Both accounts have a smaller reserve of another pallet.
+			let _ = Balances::deposit_creating(&1, 2000);
+			assert_ok!(Balances::reserve(&1, 2 * migration::LESSON2_KITTY_DEPOSIT as u64 + 400));
+			assert_ok!(Balances::reserve(&2, 600));
+
+			let old_kitties = migration::pre_migrate::<Test, DefaultInstance>().unwrap();
+			assert_eq!(old_kitties, 3);
+
+			<KittiesModule as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(migration::post_migrate::<Test, DefaultInstance>(old_kitties), Ok(()));
+			assert_eq!(KittiesModule::storage_version(), Releases::V2LinkedList);
+			assert_eq!(get_storage_value::<u32>(old, b"KittySpouse", &hash2(&0u32.encode(), &1u32.encode())), None);
+
+			assert_eq!(KittiesModule::kitties(2), Some(Kitty([3u8; 16])));
+			assert_eq!(KittiesModule::kitties_count(), 3);
+			assert_eq!(KittiesModule::live_kitties_count(), 3);
+			assert_eq!(KittiesModule::kitty_owner(0), Some(2));
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![1, 2]);
+			assert_eq!(OwnedKittiesList::iter(&2).collect::<Vec<_>>(), vec![0]);
+			assert_eq!(OwnedKittiesTest::get((1, None::<u32>)).map(|head| head.len), Some(2));
+			assert_eq!(KittiesModule::kitty_parents(2), Some((0, 1)));
+			assert_eq!(KittiesModule::kitty_parents(0), None);
+			assert!(KittyChildren::<Test>::contains_key(0, 2));
+			assert!(KittyChildren::<Test>::contains_key(1, 2));
+			assert!(KittiesModule::decode_dna(1).is_some());
+			assert_eq!(KittiesModule::kitty_deposit(0), 0);
+			assert_eq!(KittiesModule::kitty_deposit(1), 1000);
+			assert_eq!(KittiesModule::kitty_deposit(2), 1000);
+
+			// Migrated kitties work with the new layout and move their deposit
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 3, 2));
+			assert_eq!(OwnedKittiesList::iter(&3).collect::<Vec<_>>(), vec![2]);
+			assert_eq!(Balances::reserved_balance(1), 1000 + 400);
+			assert_eq!(Balances::reserved_balance(3), 1000);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![1, 3]);
+
+			// Runs only once
+			assert_eq!(<KittiesModule as OnRuntimeUpgrade>::on_runtime_upgrade(), 0);
+			assert!(migration::pre_migrate::<Test, DefaultInstance>().is_err());
+		});
+	}
+
+	#[test]
+	fn migration_backfills_lesson5_kitties() {
+		use frame_support::{traits::OnRuntimeUpgrade, storage::unhashed};
+
+		new_test_ext().execute_with(|| {
+			// Lesson5 storage: kitties 0 and 1 of account 4, kitty 1 for sale, list items without a length
+			Kitties::<Test>::insert(0, Kitty([1u8; 16]));
+			Kitties::<Test>::insert(1, Kitty([2u8; 16]));
+			KittyOwners::<Test>::insert(0, 4);
+			KittyOwners::<Test>::insert(1, 4);
+			KittiesCount::<Test>::put(2);
+			KittyPrices::<Test>::insert(1, 300);
+			let put_item = |value: Option<u32>, item: (Option<u32>, Option<u32>)| {
+				unhashed::put_raw(&OwnedKittiesTest::hashed_key_for((4, value)), &item.encode());
+			};
+			put_item(None, (Some(1), Some(0)));
+			put_item(Some(0), (None, Some(1)));
+			put_item(Some(1), (Some(0), None));
+			let traits_of_kind = |kind: u8| TraitCounts::<Test>::iter_prefix(kind).map(|(_, count)| count).sum::<u32>();
+
+			<KittiesModule as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(migration::post_migrate::<Test, DefaultInstance>(0), Ok(()));
+			assert_eq!(OwnedKittiesTest::get((4, None::<u32>)), Some(KittyLinkedItem { prev: Some(1), next: Some(0), len: 2 }));
+			assert_eq!(OwnedKittiesTest::get((4, Some(0))), Some(KittyLinkedItem { prev: None, next: Some(1), len: 0 }));
+			assert_eq!(KittiesModule::live_kitties_count(), 2);
+			assert_eq!(traits_of_kind(0), 2);
+			assert_eq!(KittiesModule::kitty_breeder(0), Some(4));
+			assert_eq!(KittiesModule::kitty_deposit(0), 0);
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(1, 300)]);
+
+			assert_ok!(KittiesModule::burn(Origin::signed(4), 0));
+			assert_eq!(KittiesModule::live_kitties_count(), 1);
+			assert_eq!(traits_of_kind(0), 1);
+			assert_ok!(KittiesModule::buy(Origin::signed(1), 1, 300));
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 1));
+			assert_eq!(KittiesModule::live_kitties_count(), 0);
+			assert_eq!(traits_of_kind(0), 0);
+			assert_eq!(OwnedKittiesList::len(&4), 0);
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![]);
+		});
+	}
+
+	#[test]
+	fn burn_does_not_underflow_counters() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			LiveKittiesCount::<Test>::kill();
+			TraitCounts::<Test>::remove_all();
+
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+			assert_eq!(KittiesModule::live_kitties_count(), 0);
+		});
+	}
+
+	#[test]
+	fn breed_and_burn_keep_lineage() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+
+			assert_eq!(KittiesModule::kitty_parents(2), Some((0, 1)));
+			assert!(KittyChildren::<Test>::contains_key(0, 2));
+
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 2));
+			assert_eq!(KittiesModule::kitty_parents(2), None);
+			assert!(!KittyChildren::<Test>::contains_key(0, 2));
+			assert!(!KittyChildren::<Test>::contains_key(1, 2));
+		});
+	}
+
+	#[test]
+	fn lease_lends_breeding_rights_until_expiry() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None));
+
+			assert_ok!(KittiesModule::lease(Origin::signed(1), 0, 2, 30, 5));
+			assert_noop!(KittiesModule::accept_lease(Origin::signed(3), 0), Error::<Test, DefaultInstance>::InvalidLessee);
+			assert_ok!(KittiesModule::accept_lease(Origin::signed(2), 0));
+
+			assert_eq!(Balances::free_balance(1), 1000 - 100 + 30);
+			assert_eq!(Balances::free_balance(2), 1000 - 100 - 30);
+			assert_eq!(KittiesModule::kitty_owner(0), Some(1));
+			assert_eq!(KittiesModule::kitty_price(0), None);
+			assert_eq!(KittiesModule::lease_offer(0), None);
+			assert_eq!(KittiesModule::lease_expiries(5), vec![0]);
+
+			// Transfer and sale are blocked for both parties, only the lessee breeds
+			assert_noop!(KittiesModule::transfer(Origin::signed(1), 3, 0), Error::<Test, DefaultInstance>::KittyLeased);
+			assert_noop!(KittiesModule::transfer(Origin::signed(2), 3, 0), Error::<Test, DefaultInstance>::RequireOwner);
+			assert_noop!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None), Error::<Test, DefaultInstance>::KittyLeased);
+			assert_noop!(KittiesModule::burn(Origin::signed(1), 0), Error::<Test, DefaultInstance>::KittyLeased);
+			assert_noop!(KittiesModule::lease(Origin::signed(1), 0, 3, 0, 10), Error::<Test, DefaultInstance>::KittyLeased);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 2), Error::<Test, DefaultInstance>::RequireOwner);
+			assert_ok!(KittiesModule::breed(Origin::signed(2), 0, 1));
+			assert_eq!(KittiesModule::kitty_owner(3), Some(2));
+
+			KittiesModule::on_initialize(5);
+			assert_eq!(KittiesModule::kitty_lease(0), None);
+			assert_eq!(KittiesModule::lease_expiries(5), Vec::<u32>::new());
+
+			assert_noop!(KittiesModule::breed(Origin::signed(2), 0, 1), Error::<Test, DefaultInstance>::RequireOwner);
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 2));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 3, 0));
+		});
+	}
+
+	#[test]
+	fn lease_failed_when_expiry_queue_full() {
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			for _ in 0..3 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			for kitty_id in 0..3 {
+				assert_ok!(KittiesModule::lease(Origin::signed(1), kitty_id, 2, 0, 5));
+			}
+			assert_noop!(KittiesModule::lease(Origin::signed(1), 0, 2, 0, 1), Error::<Test, DefaultInstance>::LeaseExpired);
+
+			assert_ok!(KittiesModule::accept_lease(Origin::signed(2), 0));
+			assert_ok!(KittiesModule::accept_lease(Origin::signed(2), 1));
+			assert_noop!(KittiesModule::accept_lease(Origin::signed(2), 2), Error::<Test, DefaultInstance>::TooManyLeaseExpiries);
+
+			// Offers do not follow the kitty
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 3, 2));
+			assert_noop!(KittiesModule::accept_lease(Origin::signed(2), 2), Error::<Test, DefaultInstance>::NoLeaseOffer);
+		});
+	}
+
+	#[test]
+	fn buy_pays_royalty_to_breeder() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_eq!(KittiesModule::kitty_breeder(0), Some(1));
+
+			// The breeder selling its own kitty keeps the whole price
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(200), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(2), 0, 200));
+			assert_eq!(Balances::free_balance(1), 1000 - 100 + 200);
+			assert_eq!(Balances::free_balance(2), 1000 - 200);
+
+			// Secondary sale, 10% of the price goes to the breeder
+			assert_ok!(KittiesModule::ask(Origin::signed(2), 0, Some(40), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(3), 0, 40));
+			assert_eq!(Balances::free_balance(1), 1000 - 100 + 200 + 4);
+			assert_eq!(Balances::free_balance(2), 1000 - 200 + 36);
+			assert_eq!(Balances::free_balance(3), 150 - 40);
+			assert_eq!(KittiesModule::kitty_breeder(0), Some(1));
+
+			// Burned kitties forget their breeder
+			assert_ok!(KittiesModule::burn(Origin::signed(3), 0));
+			assert_eq!(KittiesModule::kitty_breeder(0), None);
+		});
+	}
+
+	#[test]
+	fn buy_keeps_royalty_below_existential_deposit_of_reaped_breeder() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(Balances::transfer(Origin::signed(1), 3, 900));
+			assert!(!system::Account::<Test>::contains_key(1));
+			set_existential_deposit(50);
+
+			// A royalty of 10 can not recreate the account of the breeder
+			assert_ok!(KittiesModule::ask(Origin::signed(2), 0, Some(100), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(3), 0, 100));
+			assert_eq!(KittiesModule::kitty_owner(0), Some(3));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Sold(2, 3, 0, 100, 0, None)));
+			assert_eq!(Balances::total_balance(&1), 0);
+			assert_eq!(Balances::free_balance(2), 1000 + 100);
+
+			// A royalty of the existential deposit recreates it
+			assert_ok!(KittiesModule::ask(Origin::signed(3), 0, Some(500), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(2), 0, 500));
+			assert_eq!(Balances::free_balance(1), 50);
+			assert_eq!(Balances::free_balance(3), 150 + 900 - 100 + 450);
+			set_existential_deposit(1);
+		});
+	}
+
+	#[test]
+	fn bred_kitty_breeder_is_the_sender() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 1));
+			assert_ok!(KittiesModule::breed(Origin::signed(2), 0, 1));
+
+			assert_eq!(KittiesModule::kitty_breeder(0), Some(1));
+			assert_eq!(KittiesModule::kitty_breeder(2), Some(2));
+		});
+	}
+
+	#[test]
+	fn genesis_config_mints_kitties() {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig::<Test> {
+			kitties: vec![(1, [1u8; 16], None), (2, [2u8; 16], Some(30)), (1, [3u8; 16], Some(20))],
+		}.assimilate_storage(&mut t).unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1000)],
+		}.assimilate_storage(&mut t).unwrap();
+
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert_eq!(KittiesModule::kitties_count(), 3);
+			assert_eq!(KittiesModule::live_kitties_count(), 3);
+			assert_eq!(KittiesModule::kitties(1), Some(Kitty([2u8; 16])));
+			assert_eq!(KittiesModule::kitty_owner(2), Some(1));
+			assert_eq!(KittiesModule::kitty_deposit(0), 0);
+			assert_eq!(KittiesModule::storage_version(), Releases::V2LinkedList);
+
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![0, 2]);
+			assert_eq!(OwnedKittiesTest::get((1, None::<u32>)).map(|head| head.len), Some(2));
+			assert_eq!(OwnedKittiesList::iter(&2).collect::<Vec<_>>(), vec![1]);
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(2, 20), (1, 30)]);
+
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_eq!(OwnedKittiesList::iter(&2).collect::<Vec<_>>(), vec![1, 0]);
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_eq!(KittiesModule::kitty_owner(3), Some(1));
+		});
+	}
+
+	#[test]
+	fn create_failed_when_too_many_owned_kitties() {
+		new_test_ext().execute_with(|| {
+			for _ in 0..5 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			assert_noop!(KittiesModule::create(Origin::signed(1)), Error::<Test, DefaultInstance>::TooManyOwnedKitties);
+			assert_noop!(KittiesModule::breed(Origin::signed(1), 0, 1), Error::<Test, DefaultInstance>::TooManyOwnedKitties);
+
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 1, 2));
+		});
+	}
+
+	#[test]
+	fn create_failed_when_too_many_mints_in_block() {
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			for _ in 0..4 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			for _ in 0..3 {
+				assert_ok!(KittiesModule::create(Origin::signed(2)));
+			}
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			assert_noop!(KittiesModule::create(Origin::signed(2)), Error::<Test, DefaultInstance>::TooManyMintsInBlock);
+			assert_eq!(KittiesModule::block_mints(), (1, 8));
+
+			system::Module::<Test>::set_block_number(2);
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+			assert_eq!(KittiesModule::block_mints(), (2, 1));
+		});
+	}
+
+	#[test]
+	fn set_metadata_reserves_deposit_per_byte() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_ok!(KittiesModule::set_metadata(Origin::signed(1), 0, b"tom".to_vec(), b"ipfs://a".to_vec()));
+			assert_eq!(KittiesModule::kitty_metadata(0), Some(KittyMetadata {
+				name: b"tom".to_vec(),
+				uri: b"ipfs://a".to_vec(),
+				deposit: 22,
+			}));
+			assert_eq!(Balances::reserved_balance(1), 100 + 22);
+
+			// Overwriting refunds the previous deposit
+			assert_ok!(KittiesModule::set_metadata(Origin::signed(1), 0, b"jerry".to_vec(), vec![]));
+			assert_eq!(Balances::reserved_balance(1), 100 + 10);
+
+			assert_ok!(KittiesModule::clear_metadata(Origin::signed(1), 0));
+			assert_eq!(KittiesModule::kitty_metadata(0), None);
+			assert_eq!(Balances::reserved_balance(1), 100);
+		});
+	}
+
+	#[test]
+	fn set_metadata_failed_when_too_long_or_not_owner() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_noop!(
+				KittiesModule::set_metadata(Origin::signed(2), 0, b"tom".to_vec(), vec![]),
+				Error::<Test, DefaultInstance>::RequireOwner
+			);
+			assert_noop!(
+				KittiesModule::set_metadata(Origin::signed(1), 0, vec![b'a'; 9], vec![]),
+				Error::<Test, DefaultInstance>::NameTooLong
+			);
+			assert_noop!(
+				KittiesModule::set_metadata(Origin::signed(1), 0, vec![], vec![b'a'; 33]),
+				Error::<Test, DefaultInstance>::UriTooLong
+			);
+		});
+	}
+
+	#[test]
+	fn metadata_moves_with_transfer_and_is_refunded_on_burn() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::set_metadata(Origin::signed(1), 0, b"tom".to_vec(), vec![]));
+
+			assert_ok!(KittiesModule::transfer(Origin::signed(1), 2, 0));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::reserved_balance(2), 100 + 6);
+			assert_eq!(KittiesModule::kitty_metadata(0).map(|metadata| metadata.name), Some(b"tom".to_vec()));
+
+			assert_ok!(KittiesModule::burn(Origin::signed(2), 0));
+			assert_eq!(KittiesModule::kitty_metadata(0), None);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 1000 + 106);
+		});
+	}
+
+	#[test]
+	fn kitty_info_works() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(KittiesModule::kitty_info(0), None);
+
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::set_metadata(Origin::signed(1), 0, b"tom".to_vec(), b"ipfs://a".to_vec()));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None));
+
+			let info = KittiesModule::kitty_info(0).unwrap();
+			assert_eq!(info.owner, 1);
+			assert_eq!(info.price, Some(50));
+			assert_eq!(info.dna, KittiesModule::decode_dna(0).unwrap());
+			assert_eq!(info.name, b"tom".to_vec());
+			assert_eq!(info.uri, b"ipfs://a".to_vec());
+		});
+	}
+
+	#[test]
+	fn delayed_birth_is_finalized_on_initialize() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			set_birth_delay(2);
+			assert_ok!(KittiesModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+			set_birth_delay(0);
+
+			// Ids and deposits are taken, the kitties have no DNA yet
+			assert_eq!(KittiesModule::kitties_count(), 4);
+			assert_eq!(KittiesModule::kitties(2), None);
+			assert_eq!(KittiesModule::kitty_owner(2), None);
+			assert_eq!(KittiesModule::pending_birth_count(1), 1);
+			assert_eq!(Balances::reserved_balance(2), 100);
+			assert_eq!(KittiesModule::birth_queue(3), vec![2, 3]);
+
+			KittiesModule::on_initialize(3);
+
+			assert!(KittiesModule::kitties(2).is_some());
+			assert_eq!(KittiesModule::kitty_owner(2), Some(1));
+			assert_eq!(KittiesModule::kitty_parents(2), Some((0, 1)));
+			assert_eq!(KittiesModule::kitty_owner(3), Some(2));
+			assert_eq!(KittiesModule::kitty_deposit(3), 100);
+			assert_eq!(KittiesModule::pending_birth(2), None);
+			assert_eq!(KittiesModule::pending_birth_count(1), 0);
+			assert_eq!(KittiesModule::birth_queue(3), Vec::<u32>::new());
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![0, 1, 2]);
+		});
+	}
+
+	#[test]
+	fn claim_kitty_finalizes_due_birth() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			set_birth_delay(2);
+			for _ in 0..3 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			assert_noop!(KittiesModule::claim_kitty(Origin::signed(1), 0), Error::<Test, DefaultInstance>::BirthNotDue);
+			assert_noop!(KittiesModule::claim_kitty(Origin::signed(2), 0), Error::<Test, DefaultInstance>::RequireOwner);
+
+			system::Module::<Test>::set_block_number(3);
+			KittiesModule::on_initialize(3);
+
+			// Only MaxBirthsPerBlock births are finalized, the rest waits for the next block
+			assert!(KittiesModule::kitties(1).is_some());
+			assert_eq!(KittiesModule::kitties(2), None);
+			assert_eq!(KittiesModule::birth_queue(4), vec![2]);
+
+			assert_ok!(KittiesModule::claim_kitty(Origin::signed(1), 2));
+			assert!(KittiesModule::kitties(2).is_some());
+			assert_noop!(KittiesModule::claim_kitty(Origin::signed(1), 2), Error::<Test, DefaultInstance>::NoPendingBirth);
+
+			KittiesModule::on_initialize(4);
+			assert_eq!(KittiesModule::live_kitties_count(), 3);
+			set_birth_delay(0);
+		});
+	}
+
+	#[test]
+	fn delayed_birth_uses_randomness_of_reveal_block() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			set_birth_delay(2);
+			for _ in 0..3 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			set_birth_delay(0);
+
+			system::Module::<Test>::set_block_number(3);
+			let random = RandomnessCollectiveFlip::random(b"kitty birth");
+			KittiesModule::on_initialize(3);
+			assert_eq!(KittiesModule::pending_birth(2).and_then(|birth| birth.seed), Some(random));
+
+			// The deferred birth is claimed after the randomness changed
+			system::Module::<Test>::set_block_number(4);
+			system::Module::<Test>::set_parent_hash(H256::repeat_byte(4));
+			RandomnessCollectiveFlip::on_initialize(4);
+			assert_ne!(RandomnessCollectiveFlip::random(b"kitty birth"), random);
+			assert_ok!(KittiesModule::claim_kitty(Origin::signed(1), 2));
+
+			let seed = (random, 1u64, 2u32).using_encoded(blake2_128);
+			assert_eq!(KittiesModule::kitties(2), Some(Kitty(seed)));
+		});
+	}
+
+	#[test]
+	fn failed_birth_stays_pending() {
+		use frame_support::traits::OnInitialize;
+
+		new_test_ext().execute_with(|| {
+			system::Module::<Test>::set_block_number(1);
+			set_birth_delay(1);
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			set_birth_delay(0);
+
+			// The kitty can not be added to the list of its owner
+			assert_ok!(OwnedKittiesList::append(&1, 0));
+			system::Module::<Test>::set_block_number(2);
+			KittiesModule::on_initialize(2);
+
+			assert_eq!(KittiesModule::kitties(0), None);
+			assert!(KittiesModule::pending_birth(0).and_then(|birth| birth.seed).is_some());
+			assert_eq!(KittiesModule::pending_birth_count(1), 1);
+			assert_eq!(Balances::reserved_balance(1), 100);
+
+			assert_ok!(OwnedKittiesList::remove(&1, 0));
+			assert_ok!(KittiesModule::claim_kitty(Origin::signed(1), 0));
+			assert_eq!(KittiesModule::kitty_owner(0), Some(1));
+			assert_eq!(KittiesModule::pending_birth_count(1), 0);
+		});
+	}
+
+	#[test]
+	fn pending_births_count_as_owned_kitties() {
+		new_test_ext().execute_with(|| {
+			set_birth_delay(1);
+			for _ in 0..5 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+			}
+			assert_noop!(KittiesModule::create(Origin::signed(1)), Error::<Test, DefaultInstance>::TooManyOwnedKitties);
+			set_birth_delay(0);
+		});
+	}
+
+	#[test]
+	fn instances_have_isolated_storage() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(DogsModule::create(Origin::signed(2)));
+
+			// Both collections start at id 0 with their own owners and deposits
+			assert_eq!(KittiesModule::kitties_count(), 2);
+			assert_eq!(DogsModule::kitties_count(), 1);
+			assert_eq!(KittiesModule::kitty_owner(0), Some(1));
+			assert_eq!(DogsModule::kitty_owner(0), Some(2));
+			assert_eq!(DogsModule::kitty_owner(1), None);
+			assert_eq!(OwnedKittiesList::iter(&2).collect::<Vec<_>>(), Vec::<u32>::new());
+			assert_eq!(
+				LinkedList::<MapLayout<OwnedKitties<Test, Instance1>>, u64, u64>::iter(&2).collect::<Vec<_>>(),
+				vec![0]
+			);
+			assert_eq!(DogsModule::kitty_deposit(0), 50);
+			assert_eq!(Balances::reserved_balance(1), 200);
+			assert_eq!(Balances::reserved_balance(2), 50);
+
+			assert_ok!(DogsModule::ask(Origin::signed(2), 0, Some(10), None));
+			assert_eq!(KittiesModule::kitty_price(0), None);
+
+			// Kitty 0 of the default collection is not the dog 0
+			assert_noop!(DogsModule::burn(Origin::signed(1), 0), Error::<Test, Instance1>::RequireOwner);
+			assert_ok!(KittiesModule::burn(Origin::signed(1), 0));
+			assert!(DogsModule::kitties(0).is_some());
+			assert_eq!(DogsModule::kitty_price(0), Some(10));
+		});
+	}
+
+	#[test]
+	fn instances_decode_dna_with_their_genes() {
+		new_test_ext().execute_with(|| {
+			let mut dna = [0u8; 16];
+			dna[..5].copy_from_slice(&[16, 2, 23, 9, 14]);
+			assert_ok!(KittiesModule::insert_kitty(&1, 0, Kitty(dna), 0));
+			assert_ok!(DogsModule::insert_kitty(&1, 0, Kitty(dna), 0));
+
+			assert_eq!(KittiesModule::decode_dna(0).map(|d| d.traits.variants()), Some([1, 2, 3, 9, 4]));
+			assert_eq!(DogsModule::decode_dna(0).map(|d| d.traits.variants()), Some([2, 4, 1, 2, 4]));
+			assert_eq!(KittiesModule::trait_count(0, 1), 1);
+			assert_eq!(DogsModule::trait_count(0, 2), 1);
+			assert_eq!(DogsModule::trait_count(0, 1), 0);
+		});
+	}
+
+	#[test]
+	fn transfer_batch_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_ok!(KittiesModule::transfer_batch(Origin::signed(1), 2, vec![2, 0]));
+
+			assert_eq!(KittiesModule::kitty_owner(0), Some(2));
+			assert_eq!(KittiesModule::kitty_owner(2), Some(2));
+			assert_eq!(OwnedKittiesList::iter(&1).collect::<Vec<_>>(), vec![1]);
+			assert_eq!(OwnedKittiesList::iter(&2).collect::<Vec<_>>(), vec![2, 0]);
+			assert_eq!(Balances::reserved_balance(1), 100);
+			assert_eq!(Balances::reserved_balance(2), 200);
+		});
+	}
+
+	#[test]
+	fn transfer_batch_is_atomic() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+
+			assert_noop!(
+				KittiesModule::transfer_batch(Origin::signed(1), 3, vec![0, 1]),
+				Error::<Test, DefaultInstance>::RequireOwner
+			);
+			assert_eq!(KittiesModule::kitty_owner(0), Some(1));
+			assert_eq!(Balances::reserved_balance(3), 0);
+
+			assert_noop!(
+				KittiesModule::transfer_batch(Origin::signed(1), 3, vec![0, 0]),
+				Error::<Test, DefaultInstance>::RequireOwner
+			);
+			assert_noop!(
+				KittiesModule::transfer_batch(Origin::signed(1), 3, vec![0; 4]),
+				Error::<Test, DefaultInstance>::BatchTooLarge
+			);
+		});
+	}
+
+	#[test]
+	fn ask_batch_lists_in_price_order() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 1, Some(5), None));
+			assert_ok!(KittiesModule::ask(Origin::signed(2), 3, Some(20), None));
+
+			assert_ok!(KittiesModule::ask_batch(
+				Origin::signed(1),
+				vec![(0, Some(30), None), (1, None, None), (2, Some(10), None)],
+			));
+
+			assert_eq!(KittiesModule::kitty_price(1), None);
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(2, 10), (3, 20), (0, 30)]);
+		});
+	}
+
+	#[test]
+	fn ask_batch_uses_hints() {
+		new_test_ext().execute_with(|| {
+			for kitty_id in 0..5u32 {
+				assert_ok!(KittiesModule::create(Origin::signed(1)));
+				if kitty_id < 3 {
+					assert_ok!(KittiesModule::ask(Origin::signed(1), kitty_id, Some(5), kitty_id.checked_sub(1)));
+				}
+			}
+
+			// More than MaxAskBookWalk cheaper asks
+			assert_noop!(
+				KittiesModule::ask_batch(Origin::signed(1), vec![(3, Some(10), None), (4, Some(20), None)]),
+				Error::<Test, DefaultInstance>::AskHintTooFar
+			);
+			assert_ok!(KittiesModule::ask_batch(Origin::signed(1), vec![(3, Some(10), Some(1)), (4, Some(20), None)]));
+
+			assert_eq!(KittiesModule::cheapest_asks(10), vec![(0, 5), (1, 5), (2, 5), (3, 10), (4, 20)]);
+		});
+	}
+
+	#[test]
+	fn ask_batch_is_atomic() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::create(Origin::signed(2)));
+
+			assert_noop!(
+				KittiesModule::ask_batch(Origin::signed(1), vec![(0, Some(10), None), (1, Some(20), None)]),
+				Error::<Test, DefaultInstance>::RequireOwner
+			);
+			assert_noop!(
+				KittiesModule::ask_batch(Origin::signed(1), vec![(0, Some(10), None), (0, None, None)]),
+				Error::<Test, DefaultInstance>::DuplicateKitty
+			);
+			assert_noop!(
+				KittiesModule::ask_batch(Origin::signed(1), vec![(0, None, None); 4]),
+				Error::<Test, DefaultInstance>::BatchTooLarge
+			);
+			assert_eq!(KittiesModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn gift_transfers_kitty() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+
+			assert_noop!(
+				KittiesModule::gift(Origin::signed(2), 3, 0, H256::repeat_byte(1)),
+				Error::<Test, DefaultInstance>::RequireOwner
+			);
+			assert_ok!(KittiesModule::gift(Origin::signed(1), 2, 0, H256::repeat_byte(1)));
+
+			assert_eq!(KittiesModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::reserved_balance(2), 100);
+		});
+	}
+
+	#[test]
+	fn buy_records_last_sale_price() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(KittiesModule::create(Origin::signed(1)));
+			assert_ok!(KittiesModule::ask(Origin::signed(1), 0, Some(50), None));
+			assert_ok!(KittiesModule::buy(Origin::signed(2), 0, 50));
+			assert_eq!(KittiesModule::last_sale_price(0), Some(50));
+			// No royalty when the breeder sells
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Sold(1, 2, 0, 50, 0, None)));
+
+			assert_ok!(KittiesModule::ask(Origin::signed(2), 0, Some(80), None));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Ask(2, 0, Some(80), None)));
+			assert_ok!(KittiesModule::ask(Origin::signed(2), 0, Some(90), None));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Ask(2, 0, Some(90), Some(80))));
+			assert_ok!(KittiesModule::buy(Origin::signed(3), 0, 90));
+			assert_eq!(KittiesModule::last_sale_price(0), Some(90));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Sold(2, 3, 0, 90, 9, Some(50))));
+
+			assert_ok!(KittiesModule::burn(Origin::signed(3), 0));
+			assert_eq!(KittiesModule::last_sale_price(0), None);
+		});
+	}
+
 }
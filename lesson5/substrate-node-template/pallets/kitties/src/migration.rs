@@ -0,0 +1,229 @@
+//! Migration from the `KittiesModule` storage of lesson2, where owned kitties are a
+//! `double_map AccountId, KittyIndex => KittyIndex`, to the linked list layout.
+//!
+//! Kitties, owners, `KittiesCount` and the lineage (`KittyParents`, `KittyChildren`)
+//! are moved over, `KittySpouse` is dropped as it can be rebuilt from the lineage.
+//! The old pallet reserved `LESSON2_KITTY_DEPOSIT` for each created or bred kitty without
+//! tracking it per kitty, and a transfer released it without reserving it from the new owner.
+//! Each whole `LESSON2_KITTY_DEPOSIT` its owner still has reserved goes to one migrated kitty,
+//! a reserve smaller than that was not made by the old pallet and is left alone.
+//!
+//! Items of the lists already in the linked list layout of lesson5 get a length, the head
+//! items the length of their list. Lesson5 reserved no deposit for its kitties.
+//!
+//! The live count, trait counts, breeders and ask book are then built from all the kitties.
+//! Neither layout records who created a kitty, the owner at the upgrade becomes its breeder.
+
+use codec::{Encode, Decode, FullCodec};
+use frame_support::{
+	StorageValue, StorageMap, StorageDoubleMap, IterableStorageMap, weights::Weight,
+	traits::{Get, ReservableCurrency},
+	storage::migration::{StorageIterator, take_storage_value, remove_storage_prefix},
+};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+use linked_list::LinkedItem;
+use crate::{
+	Trait, Instance, Kitty, KittyTraits, Kitties, KittiesCount, LiveKittiesCount, KittyOwners, KittyParents,
+	KittyChildren, BreedCounts, KittyDeposits, KittyBreeders, KittyPrices, OwnedKitties, TraitCounts, StorageVersion,
+	OwnedKittiesList, AskBookList, BalanceOf,
+};
+
+/// Module prefix of the old storage.
+pub const OLD_MODULE: &[u8] = b"KittiesModule";
+
+/// Deposit reserved by the old pallet for each created or bred kitty.
+pub const LESSON2_KITTY_DEPOSIT: u32 = 1000;
+
+/// Storage layout versions of the pallet.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, Default)]
+pub enum Releases {
+	/// Owned kitties stored in a double map, under the `KittiesModule` prefix.
+	#[default]
+	V1DoubleMap,
+	/// Owned kitties stored in linked lists.
+	V2LinkedList,
+}
+
+/// Decode a key hashed with `blake2_128_concat` and advance past it.
+fn decode_blake2_128_concat<K: Decode>(key: &mut &[u8]) -> Option<K> {
+	if key.len() < 16 {
+		return None;
+	}
+	*key = &key[16..];
+	K::decode(key).ok()
+}
+
+/// Drain an old map keyed with `blake2_128_concat`.
+fn drain_map<K: Decode, V: FullCodec>(item: &[u8]) -> Vec<(K, V)> {
+	StorageIterator::<V>::new(OLD_MODULE, item)
+		.drain()
+		.filter_map(|(key, value)| decode_blake2_128_concat(&mut &key[..]).map(|k| (k, value)))
+		.collect()
+}
+
+/// Drain an old double map keyed with `blake2_128_concat` twice.
+fn drain_double_map<K1: Decode, K2: Decode, V: FullCodec>(item: &[u8]) -> Vec<(K1, K2, V)> {
+	StorageIterator::<V>::new(OLD_MODULE, item)
+		.drain()
+		.filter_map(|(key, value)| {
+			let key = &mut &key[..];
+			let k1 = decode_blake2_128_concat(key)?;
+			let k2 = decode_blake2_128_concat(key)?;
+			Some((k1, k2, value))
+		})
+		.collect()
+}
+
+/// Number of kitties in the old storage, checked before migrating.
+pub fn pre_migrate<T: Trait<I>, I: Instance>() -> Result<u32, &'static str> {
+	if StorageVersion::<I>::get() != Releases::V1DoubleMap {
+		return Err("Kitties storage is already migrated");
+	}
+	Ok(StorageIterator::<Kitty>::new(OLD_MODULE, b"Kitties").count() as u32)
+}
+
+/// Check the migrated storage, `old_kitties` is the result of `pre_migrate`.
+pub fn post_migrate<T: Trait<I>, I: Instance>(old_kitties: u32) -> Result<(), &'static str> {
+	if StorageVersion::<I>::get() != Releases::V2LinkedList {
+		return Err("Kitties storage version is not updated");
+	}
+	for item in &[&b"Kitties"[..], b"KittiesCount", b"KittyOwners", b"OwnedKitties", b"KittyParents",
+		b"KittyChildren", b"KittySpouse"] {
+		if StorageIterator::<Vec<u8>>::new(OLD_MODULE, item).next().is_some() {
+			return Err("Old kitties storage is not empty");
+		}
+	}
+
+	let mut owned = 0u32;
+	for (kitty_id, owner) in KittyOwners::<T, I>::iter() {
+		if !Kitties::<T, I>::contains_key(kitty_id) {
+			return Err("Owned kitty does not exist");
+		}
+		if !<OwnedKittiesList<T, I>>::contains(&owner, kitty_id) {
+			return Err("Kitty is missing in the owned kitties of its owner");
+		}
+		owned += 1;
+	}
+	if owned < old_kitties || Kitties::<T, I>::iter().count() as u32 != owned {
+		return Err("Kitties are missing an owner");
+	}
+
+	Ok(())
+}
+
+/// Move the old storage to the linked list layout and bump the storage version.
+pub fn migrate_to_linked_list<T: Trait<I>, I: Instance>() -> Weight {
+	let mut reads = 1u64;
+	let mut writes = 1u64;
+
+	// The lists of lesson5 were written before their items kept a length
+	OwnedKitties::<T, I>::translate::<(Option<T::KittyIndex>, Option<T::KittyIndex>), _>(|_, (prev, next)| {
+		Some(LinkedItem { prev, next, len: 0 })
+	});
+	let keys = OwnedKitties::<T, I>::iter().map(|(key, _)| key).collect::<Vec<_>>();
+	for (owner, _) in keys.iter().filter(|(_, value)| value.is_none()) {
+		let len = <OwnedKittiesList<T, I>>::iter(owner).count() as u32;
+		OwnedKitties::<T, I>::mutate((owner.clone(), None::<T::KittyIndex>), |head| {
+			if let Some(head) = head {
+				head.len = len;
+			}
+		});
+		reads += len as u64 + 1;
+		writes += 1;
+	}
+	reads += keys.len() as u64 * 2;
+	writes += keys.len() as u64;
+
+	let kitties = drain_map::<T::KittyIndex, Kitty>(b"Kitties");
+	for (kitty_id, kitty) in kitties.iter() {
+		Kitties::<T, I>::insert(kitty_id, kitty);
+	}
+	reads += kitties.len() as u64;
+	writes += kitties.len() as u64 * 2;
+
+	if let Some(count) = take_storage_value::<T::KittyIndex>(OLD_MODULE, b"KittiesCount", &[]) {
+		KittiesCount::<T, I>::put(count);
+		reads += 1;
+		writes += 1;
+	}
+
+	let owners = drain_map::<T::KittyIndex, T::AccountId>(b"KittyOwners");
+	for (kitty_id, owner) in owners.iter() {
+		KittyOwners::<T, I>::insert(kitty_id, owner);
+	}
+	reads += owners.len() as u64;
+	writes += owners.len() as u64 * 2;
+
+	// Hashed keys are in random order, keep the kitties of each owner sorted by id
+	let mut owned = drain_double_map::<T::AccountId, T::KittyIndex, T::KittyIndex>(b"OwnedKitties");
+	owned.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+	let mut reserved: Option<(&T::AccountId, BalanceOf<T, I>)> = None;
+	for (owner, kitty_id, _) in owned.iter() {
+		// Duplicates can not happen in a double map
+		let _ = <OwnedKittiesList<T, I>>::append(owner, *kitty_id);
+
+		// The old pallet did not record which kitty a reserve is for, the kitties of an owner
+		// get one in order of id until the reserve runs out
+		let mut remaining = match reserved {
+			Some((account, remaining)) if account == owner => remaining,
+			_ => T::Currency::reserved_balance(owner),
+		};
+		let deposit: BalanceOf<T, I> = LESSON2_KITTY_DEPOSIT.into();
+		if remaining >= deposit {
+			remaining -= deposit;
+			KittyDeposits::<T, I>::insert(kitty_id, deposit);
+		}
+		reserved = Some((owner, remaining));
+	}
+	reads += owned.len() as u64 * 4;
+	writes += owned.len() as u64 * 5;
+
+	// Created kitties have `(0, 0)` as parents in the old storage, bred kitties two different ones
+	let parents = drain_map::<T::KittyIndex, (T::KittyIndex, T::KittyIndex)>(b"KittyParents");
+	for (kitty_id, (parent1, parent2)) in parents.iter() {
+		if parent1 != parent2 {
+			KittyParents::<T, I>::insert(kitty_id, (parent1, parent2));
+		}
+	}
+	let children = drain_double_map::<T::KittyIndex, T::KittyIndex, T::KittyIndex>(b"KittyChildren");
+	for (parent, child, _) in children.iter() {
+		KittyChildren::<T, I>::insert(parent, child, ());
+		BreedCounts::<T, I>::mutate(parent, |count| *count += 1);
+	}
+	reads += (parents.len() + children.len() * 2) as u64;
+	writes += (parents.len() + children.len() * 3) as u64;
+
+	remove_storage_prefix(OLD_MODULE, b"KittySpouse", &[]);
+	writes += 1;
+
+	// Neither layout kept these, build them from the kitties of both
+	let mut live = 0u32;
+	for (kitty_id, kitty) in Kitties::<T, I>::iter() {
+		for (kind, variant) in KittyTraits::decode_dna(&kitty.0, &T::Genes::get()).variants().iter().enumerate() {
+			TraitCounts::<T, I>::mutate(kind as u8, variant, |count| *count += 1u32.into());
+		}
+		if !KittyBreeders::<T, I>::contains_key(kitty_id) {
+			if let Some(owner) = KittyOwners::<T, I>::get(kitty_id) {
+				KittyBreeders::<T, I>::insert(kitty_id, owner);
+			}
+		}
+		live += 1;
+	}
+	LiveKittiesCount::<T, I>::put(T::KittyIndex::from(live));
+	reads += live as u64 * 3;
+	writes += live as u64 * 6 + 1;
+
+	// Lesson5 priced kitties without an ask book
+	let priced = KittyPrices::<T, I>::iter().map(|(kitty_id, _)| kitty_id).collect::<Vec<_>>();
+	for kitty_id in priced.iter() {
+		// Only fails for a kitty listed already
+		let _ = <AskBookList<T, I>>::insert(&(), *kitty_id);
+	}
+	reads += priced.len() as u64 * (priced.len() as u64 + 2);
+	writes += priced.len() as u64 * 4;
+
+	StorageVersion::<I>::put(Releases::V2LinkedList);
+
+	T::DbWeight::get().reads_writes(reads, writes)
+}
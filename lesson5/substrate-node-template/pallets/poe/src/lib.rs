@@ -1,13 +1,16 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-/// A module for proof of existence
+//! A module for proof of existence
 
 use frame_support::{
 	decl_module, decl_storage, decl_event, decl_error, ensure,
-	dispatch::DispatchResult,
+	dispatch::DispatchResult, weights::Weight, traits::Get, IterableStorageMap,
 };
 use frame_system::{self as system, ensure_signed};
+use codec::{Encode, Decode};
+use sp_runtime::RuntimeDebug;
 use sp_std::vec::Vec;
+use linked_list::{LinkedList, LinkedItem, LinkedListError, DoubleMapLayout};
 
 #[cfg(test)]
 mod mock;
@@ -23,10 +26,27 @@ pub trait Trait: system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
+/// Storage layout versions of the pallet.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, Default)]
+pub enum Releases {
+	/// Claims stored in `Proofs` only.
+	#[default]
+	V1Proofs,
+	/// Claims of each owner also linked in `OwnedClaims`.
+	V2OwnedClaims,
+}
+
+type OwnedClaimsList<T> = LinkedList<DoubleMapLayout<OwnedClaims<T>>, <T as system::Trait>::AccountId, Vec<u8>>;
+
 // This pallet's storage items.
 decl_storage! {
 	trait Store for Module<T: Trait> as PoeModule {
 		Proofs get(fn proof): map hasher(blake2_128_concat) Vec<u8> => (T::AccountId, T::BlockNumber);
+		/// Claims of each owner in a linked list.
+		OwnedClaims get(fn owned_claims): double_map hasher(blake2_128_concat) T::AccountId,
+			hasher(blake2_128_concat) Option<Vec<u8>> => Option<LinkedItem<Vec<u8>>>;
+		/// Storage layout version, chains started before `OwnedClaims` have none.
+		StorageVersion get(fn storage_version) build(|_| Releases::V2OwnedClaims): Releases;
 	}
 }
 
@@ -49,6 +69,17 @@ decl_error! {
 	}
 }
 
+impl<T: Trait> From<LinkedListError> for Error<T> {
+	fn from(e: LinkedListError) -> Self {
+		match e {
+			LinkedListError::ValueNotFound => Error::<T>::ClaimNotExist,
+			LinkedListError::ValueAlreadyExists => Error::<T>::ProofAlreadyExist,
+			// Claim lists are not sorted, no hint is ever given
+			LinkedListError::InvalidHint | LinkedListError::HintTooFar => Error::<T>::ClaimNotExist,
+		}
+	}
+}
+
 // The pallet's dispatchable functions.
 decl_module! {
 	/// The module declaration.
@@ -62,6 +93,23 @@ decl_module! {
 		// this is needed only if you are using events in your pallet
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() -> Weight {
+			if Self::storage_version() != Releases::V1Proofs {
+				return 0;
+			}
+
+			// Link the claims created before the lists, in no particular order
+			let claims = Proofs::<T>::iter().collect::<Vec<_>>();
+			for (claim, (owner, _)) in claims.iter() {
+				// Only fails for a claim linked already
+				let _ = OwnedClaimsList::<T>::append(owner, claim.clone());
+			}
+			StorageVersion::put(Releases::V2OwnedClaims);
+
+			let count = claims.len() as Weight;
+			T::DbWeight::get().reads_writes(1 + count * 4, 1 + count * 4)
+		}
+
 		#[weight = 10_000]
 		pub fn create_claim(origin, claim: Vec<u8>) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
@@ -70,6 +118,7 @@ decl_module! {
 
 			ensure!(!Proofs::<T>::contains_key(&claim), Error::<T>::ProofAlreadyExist);
 
+			OwnedClaimsList::<T>::append(&sender, claim.clone()).map_err(Error::<T>::from)?;
 			Proofs::<T>::insert(&claim, (sender.clone(), system::Module::<T>::block_number()));
 
 			Self::deposit_event(RawEvent::ClaimCreated(sender, claim));
@@ -87,6 +136,7 @@ decl_module! {
 
 			ensure!(owner == sender, Error::<T>::NotClaimOwner);
 
+			OwnedClaimsList::<T>::remove(&sender, claim.clone()).map_err(Error::<T>::from)?;
 			Proofs::<T>::remove(&claim);
 
 			Self::deposit_event(RawEvent::ClaimRevoked(sender, claim));
@@ -104,6 +154,10 @@ decl_module! {
 
 			ensure!(owner == sender, Error::<T>::NotClaimOwner);
 
+			if to != sender {
+				OwnedClaimsList::<T>::move_to(&sender, &to, claim.clone()).map_err(Error::<T>::from)?;
+			}
+
 			Proofs::<T>::remove(&claim);
 
 			Proofs::<T>::insert(&claim, (to.clone(), block_number));
@@ -28,10 +28,7 @@ fn create_claim_failed_when_claim_already_exist() {
 #[test]
 fn create_claim_failed_when_claim_too_long() {
     new_test_ext().execute_with(|| {
-        let mut claim = vec![];
-        for _ in 0..=MAX_CLAIM_SIZE {
-            claim.push(1);
-        }
+        let claim = vec![1; MAX_CLAIM_SIZE + 1];
         assert_noop!(
             PoeModule::create_claim(Origin::signed(1), claim.clone()),
             Error::<Test>::ClaimTooLong
@@ -90,4 +87,52 @@ fn transfer_claim_failed_when_is_not_claim_owner() {
             Error::<Test>::NotClaimOwner
         );
     })
-}
\ No newline at end of file
+}
+
+#[test]
+fn owned_claims_follow_claim_owner() {
+    new_test_ext().execute_with(|| {
+        let owned = |account| OwnedClaimsList::<Test>::iter(&account).collect::<Vec<_>>();
+        assert_ok!(PoeModule::create_claim(Origin::signed(1), vec![0, 1]));
+        assert_ok!(PoeModule::create_claim(Origin::signed(1), vec![0, 2]));
+        assert_ok!(PoeModule::create_claim(Origin::signed(1), vec![0, 3]));
+        assert_eq!(owned(1), vec![vec![0, 1], vec![0, 2], vec![0, 3]]);
+
+        assert_ok!(PoeModule::transfer_claim(Origin::signed(1), vec![0, 2], 2));
+        assert_eq!(owned(1), vec![vec![0, 1], vec![0, 3]]);
+        assert_eq!(owned(2), vec![vec![0, 2]]);
+
+        assert_ok!(PoeModule::revoke_claim(Origin::signed(1), vec![0, 1]));
+        assert_eq!(owned(1), vec![vec![0, 3]]);
+        assert_eq!(OwnedClaimsList::<Test>::len(&1), 1);
+        assert_eq!(OwnedClaims::<Test>::get(1, Some(vec![0, 1])), None);
+    })
+}
+
+#[test]
+fn runtime_upgrade_links_existing_claims() {
+    new_test_ext().execute_with(|| {
+        use frame_support::traits::OnRuntimeUpgrade;
+
+        // Claims created before the owned claims lists
+        Proofs::<Test>::insert(vec![0, 1], (1, 0));
+        Proofs::<Test>::insert(vec![0, 2], (1, 0));
+        Proofs::<Test>::insert(vec![0, 3], (2, 0));
+        assert_eq!(PoeModule::storage_version(), Releases::V1Proofs);
+
+        <PoeModule as OnRuntimeUpgrade>::on_runtime_upgrade();
+        assert_eq!(PoeModule::storage_version(), Releases::V2OwnedClaims);
+        assert_eq!(OwnedClaimsList::<Test>::len(&1), 2);
+        assert_eq!(OwnedClaimsList::<Test>::iter(&2).collect::<Vec<_>>(), vec![vec![0, 3]]);
+
+        assert_ok!(PoeModule::revoke_claim(Origin::signed(1), vec![0, 1]));
+        assert_ok!(PoeModule::transfer_claim(Origin::signed(1), vec![0, 2], 2));
+        assert_eq!(OwnedClaimsList::<Test>::len(&1), 0);
+        assert_eq!(OwnedClaimsList::<Test>::len(&2), 2);
+
+        // Runs only once
+        Proofs::<Test>::insert(vec![0, 4], (1, 0));
+        <PoeModule as OnRuntimeUpgrade>::on_runtime_upgrade();
+        assert_eq!(OwnedClaimsList::<Test>::len(&1), 0);
+    })
+}
@@ -0,0 +1,402 @@
+//! Doubly linked lists stored in a pallet storage map.
+//!
+//! Every list is identified by a `Key` and holds unique `Value`s. The items can be
+//! stored either in a `StorageMap<(Key, Option<Value>), LinkedItem<Value>>` (`MapLayout`)
+//! or in a `StorageDoubleMap<Key, Option<Value>, LinkedItem<Value>>` (`DoubleMapLayout`).
+//!
+//! - `LinkedList` keeps the values in insertion order, or in any order the caller
+//!   builds with `insert_after` / `insert_before`.
+//! - `SortedLinkedList` keeps the values sorted by a weight derived from each value.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{StorageMap, StorageDoubleMap, Parameter};
+use sp_runtime::{RuntimeDebug, traits::{Member, Convert}};
+use codec::{Encode, Decode};
+use sp_std::{prelude::*, marker::PhantomData};
+
+#[cfg(test)]
+mod tests;
+
+/// `prev` and `next` are None at the ends of the list, which link to the head item.
+/// The head item links to the last (`prev`) and the first (`next`) value and keeps
+/// the length of the list. `len` is always 0 for other items.
+///
+/// Items written before `len` was added encode as `(prev, next)` and have to be migrated.
+#[derive(Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+pub struct LinkedItem<Value> {
+	pub prev: Option<Value>,
+	pub next: Option<Value>,
+	pub len: u32,
+}
+
+/// Pallets map these to their own module errors.
+#[derive(RuntimeDebug, PartialEq, Eq)]
+pub enum LinkedListError {
+	/// The value is not in the list.
+	ValueNotFound,
+	/// The value is already in the list.
+	ValueAlreadyExists,
+	/// The position hint is not in the list or has a higher weight than the value.
+	InvalidHint,
+	/// The position hint is too far from where the value belongs.
+	HintTooFar,
+}
+
+/// Where the items of a list are stored. `None` is the head item of the list.
+pub trait LinkedListStorage<Key, Value> {
+	fn get(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>>;
+	fn insert(key: &Key, value: &Option<Value>, item: LinkedItem<Value>);
+	fn take(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>>;
+	fn contains_key(key: &Key, value: &Option<Value>) -> bool;
+}
+
+/// Items stored in a map keyed by `(Key, Option<Value>)`.
+pub struct MapLayout<Storage>(PhantomData<Storage>);
+
+impl<Storage, Key, Value> LinkedListStorage<Key, Value> for MapLayout<Storage> where
+	Value: Parameter + Member,
+	Key: Parameter,
+	Storage: StorageMap<(Key, Option<Value>), LinkedItem<Value>, Query = Option<LinkedItem<Value>>>,
+{
+	fn get(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>> {
+		Storage::get((key, value))
+	}
+
+	fn insert(key: &Key, value: &Option<Value>, item: LinkedItem<Value>) {
+		Storage::insert((key, value), item)
+	}
+
+	fn take(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>> {
+		Storage::take((key, value))
+	}
+
+	fn contains_key(key: &Key, value: &Option<Value>) -> bool {
+		Storage::contains_key((key, value))
+	}
+}
+
+/// Items stored in a double map keyed by `Key, Option<Value>`.
+/// All the items of a list can be iterated or removed by prefix.
+pub struct DoubleMapLayout<Storage>(PhantomData<Storage>);
+
+impl<Storage, Key, Value> LinkedListStorage<Key, Value> for DoubleMapLayout<Storage> where
+	Value: Parameter + Member,
+	Key: Parameter,
+	Storage: StorageDoubleMap<Key, Option<Value>, LinkedItem<Value>, Query = Option<LinkedItem<Value>>>,
+{
+	fn get(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>> {
+		Storage::get(key, value)
+	}
+
+	fn insert(key: &Key, value: &Option<Value>, item: LinkedItem<Value>) {
+		Storage::insert(key, value, item)
+	}
+
+	fn take(key: &Key, value: &Option<Value>) -> Option<LinkedItem<Value>> {
+		Storage::take(key, value)
+	}
+
+	fn contains_key(key: &Key, value: &Option<Value>) -> bool {
+		Storage::contains_key(key, value)
+	}
+}
+
+/// A list of values in insertion order.
+pub struct LinkedList<Layout, Key, Value>(PhantomData<(Layout, Key, Value)>);
+
+/// Iterates the values of a linked list from the first one to the last one.
+pub struct LinkedListIterator<Layout, Key, Value> {
+	key: Key,
+	next: Option<Value>,
+	_phantom: PhantomData<Layout>,
+}
+
+impl<Layout, Key, Value> Iterator for LinkedListIterator<Layout, Key, Value> where
+	Value: Parameter + Member,
+	Key: Parameter,
+	Layout: LinkedListStorage<Key, Value>,
+{
+	type Item = Value;
+
+	fn next(&mut self) -> Option<Value> {
+		let current = self.next.take()?;
+		self.next = <LinkedList<Layout, Key, Value>>::read(&self.key, &Some(current.clone())).next;
+		Some(current)
+	}
+}
+
+impl<Layout, Key, Value> LinkedList<Layout, Key, Value> where
+	Value: Parameter + Member,
+	Key: Parameter,
+	Layout: LinkedListStorage<Key, Value>,
+{
+	fn read_head(key: &Key) -> LinkedItem<Value> {
+		Self::read(key, &None)
+	}
+
+	fn write_head(key: &Key, item: LinkedItem<Value>) {
+		Self::write(key, &None, item);
+	}
+
+	fn read(key: &Key, value: &Option<Value>) -> LinkedItem<Value> {
+		Layout::get(key, value).unwrap_or_else(|| LinkedItem {
+			prev: None,
+			next: None,
+			len: 0,
+		})
+	}
+
+	fn write(key: &Key, value: &Option<Value>, item: LinkedItem<Value>) {
+		Layout::insert(key, value, item);
+	}
+
+	/// Link a new value between `prev` and `next`, which must be adjacent.
+	fn link(key: &Key, value: Value, prev: Option<Value>, next: Option<Value>) {
+		// `prev` and `next` are both the head for an empty list,
+		// so always read an item after the previous write.
+		let mut prev_item = Self::read(key, &prev);
+		prev_item.next = Some(value.clone());
+		Self::write(key, &prev, prev_item);
+
+		let mut next_item = Self::read(key, &next);
+		next_item.prev = Some(value.clone());
+		Self::write(key, &next, next_item);
+
+		Self::write(key, &Some(value), LinkedItem {
+			prev,
+			next,
+			len: 0,
+		});
+
+		let mut head = Self::read_head(key);
+		head.len += 1;
+		Self::write_head(key, head);
+	}
+
+	/// Unlink an existing value and join its neighbours.
+	fn unlink(key: &Key, value: Value) -> Result<(), LinkedListError> {
+		let item = Layout::take(key, &Some(value)).ok_or(LinkedListError::ValueNotFound)?;
+
+		let mut prev = Self::read(key, &item.prev);
+		prev.next = item.next.clone();
+		Self::write(key, &item.prev, prev);
+
+		let mut next = Self::read(key, &item.next);
+		next.prev = item.prev;
+		Self::write(key, &item.next, next);
+
+		// An item without its length counted in the head is not migrated, do not underflow
+		let mut head = Self::read_head(key);
+		head.len = head.len.saturating_sub(1);
+		Self::write_head(key, head);
+
+		Ok(())
+	}
+
+	fn ensure_absent(key: &Key, value: &Value) -> Result<(), LinkedListError> {
+		if Self::contains(key, value.clone()) {
+			Err(LinkedListError::ValueAlreadyExists)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Number of values in the list.
+	pub fn len(key: &Key) -> u32 {
+		Self::read_head(key).len
+	}
+
+	pub fn contains(key: &Key, value: Value) -> bool {
+		Layout::contains_key(key, &Some(value))
+	}
+
+	pub fn first(key: &Key) -> Option<Value> {
+		Self::read_head(key).next
+	}
+
+	pub fn last(key: &Key) -> Option<Value> {
+		Self::read_head(key).prev
+	}
+
+	/// The item of a value in the list, None if the value is not in the list.
+	pub fn item(key: &Key, value: Value) -> Option<LinkedItem<Value>> {
+		Layout::get(key, &Some(value))
+	}
+
+	pub fn append(key: &Key, value: Value) -> Result<(), LinkedListError> {
+		Self::ensure_absent(key, &value)?;
+
+		let head = Self::read_head(key);
+		Self::link(key, value, head.prev, None);
+
+		Ok(())
+	}
+
+	/// Insert `value` right after `after`, which must be in the list.
+	pub fn insert_after(key: &Key, after: Value, value: Value) -> Result<(), LinkedListError> {
+		let item = Layout::get(key, &Some(after.clone())).ok_or(LinkedListError::ValueNotFound)?;
+		Self::ensure_absent(key, &value)?;
+
+		Self::link(key, value, Some(after), item.next);
+
+		Ok(())
+	}
+
+	/// Insert `value` right before `before`, which must be in the list.
+	pub fn insert_before(key: &Key, before: Value, value: Value) -> Result<(), LinkedListError> {
+		let item = Layout::get(key, &Some(before.clone())).ok_or(LinkedListError::ValueNotFound)?;
+		Self::ensure_absent(key, &value)?;
+
+		Self::link(key, value, item.prev, Some(before));
+
+		Ok(())
+	}
+
+	/// Remove and return the first value.
+	pub fn pop_front(key: &Key) -> Option<Value> {
+		let first = Self::read_head(key).next?;
+		Self::unlink(key, first.clone()).ok()?;
+		Some(first)
+	}
+
+	/// Move a value to the end of another list, nothing is changed on error.
+	pub fn move_to(key_from: &Key, key_to: &Key, value: Value) -> Result<(), LinkedListError> {
+		if !Self::contains(key_from, value.clone()) {
+			return Err(LinkedListError::ValueNotFound);
+		}
+		Self::ensure_absent(key_to, &value)?;
+
+		Self::unlink(key_from, value.clone())?;
+		Self::append(key_to, value)
+	}
+
+	pub fn iter(key: &Key) -> LinkedListIterator<Layout, Key, Value> {
+		Self::iter_from(key, Self::read_head(key).next)
+	}
+
+	fn iter_from(key: &Key, next: Option<Value>) -> LinkedListIterator<Layout, Key, Value> {
+		LinkedListIterator {
+			key: key.clone(),
+			next,
+			_phantom: Default::default(),
+		}
+	}
+
+	/// Read at most `count` values following `start_after`, from the first value if None.
+	/// Returns nothing if `start_after` is not in the list.
+	pub fn page(key: &Key, start_after: Option<Value>, count: u32) -> Vec<Value> {
+		let next = match start_after {
+			None => Self::read_head(key).next,
+			Some(value) => match Layout::get(key, &Some(value)) {
+				Some(item) => item.next,
+				None => return Vec::new(),
+			},
+		};
+
+		Self::iter_from(key, next).take(count as usize).collect()
+	}
+
+	pub fn remove(key: &Key, value: Value) -> Result<(), LinkedListError> {
+		Self::unlink(key, value)
+	}
+}
+
+/// A list of values kept in ascending order of `WeightOf::convert(value)`.
+/// Values with the same weight keep their insertion order.
+pub struct SortedLinkedList<Layout, Key, Value, Weight, WeightOf>(
+	PhantomData<(Layout, Key, Value, Weight, WeightOf)>
+);
+
+impl<Layout, Key, Value, Weight, WeightOf> SortedLinkedList<Layout, Key, Value, Weight, WeightOf> where
+	Value: Parameter + Member,
+	Key: Parameter,
+	Layout: LinkedListStorage<Key, Value>,
+	Weight: Ord,
+	WeightOf: Convert<Value, Weight>,
+{
+	/// Insert a value after all the values with a lower or equal weight.
+	/// Walks the whole list in the worst case.
+	pub fn insert(key: &Key, value: Value) -> Result<(), LinkedListError> {
+		let weight = WeightOf::convert(value.clone());
+		let before = <LinkedList<Layout, Key, Value>>::iter(key)
+			.find(|other| WeightOf::convert(other.clone()) > weight);
+
+		match before {
+			Some(before) => <LinkedList<Layout, Key, Value>>::insert_before(key, before, value),
+			None => <LinkedList<Layout, Key, Value>>::append(key, value),
+		}
+	}
+
+	/// Insert a value after all the values with a lower or equal weight, walking at most
+	/// `max_walk` items forward from `hint`. `hint` must be in the list with a weight not
+	/// higher than the value, None to walk from the first value.
+	/// Clients find a good hint off-chain, the hint is only checked here.
+	pub fn insert_with_hint(
+		key: &Key,
+		value: Value,
+		hint: Option<Value>,
+		max_walk: u32,
+	) -> Result<(), LinkedListError> {
+		<LinkedList<Layout, Key, Value>>::ensure_absent(key, &value)?;
+
+		let weight = WeightOf::convert(value.clone());
+		let mut next = match &hint {
+			None => <LinkedList<Layout, Key, Value>>::first(key),
+			Some(hint) => {
+				let item = <LinkedList<Layout, Key, Value>>::item(key, hint.clone())
+					.ok_or(LinkedListError::InvalidHint)?;
+				if WeightOf::convert(hint.clone()) > weight {
+					return Err(LinkedListError::InvalidHint);
+				}
+				item.next
+			},
+		};
+		let mut prev = hint;
+
+		let mut walked = 0;
+		while let Some(current) = next.clone() {
+			if WeightOf::convert(current.clone()) > weight {
+				break;
+			}
+			if walked == max_walk {
+				return Err(LinkedListError::HintTooFar);
+			}
+			walked += 1;
+			next = <LinkedList<Layout, Key, Value>>::read(key, &Some(current.clone())).next;
+			prev = Some(current);
+		}
+
+		<LinkedList<Layout, Key, Value>>::link(key, value, prev, next);
+
+		Ok(())
+	}
+
+	/// Remove a value, must be called before the weight of the value changes.
+	pub fn remove(key: &Key, value: Value) -> Result<(), LinkedListError> {
+		<LinkedList<Layout, Key, Value>>::remove(key, value)
+	}
+
+	pub fn len(key: &Key) -> u32 {
+		<LinkedList<Layout, Key, Value>>::len(key)
+	}
+
+	pub fn contains(key: &Key, value: Value) -> bool {
+		<LinkedList<Layout, Key, Value>>::contains(key, value)
+	}
+
+	/// Remove and return the value with the lowest weight.
+	pub fn pop_front(key: &Key) -> Option<Value> {
+		<LinkedList<Layout, Key, Value>>::pop_front(key)
+	}
+
+	/// Iterates from the lowest weight to the highest.
+	pub fn iter(key: &Key) -> LinkedListIterator<Layout, Key, Value> {
+		<LinkedList<Layout, Key, Value>>::iter(key)
+	}
+
+	pub fn page(key: &Key, start_after: Option<Value>, count: u32) -> Vec<Value> {
+		<LinkedList<Layout, Key, Value>>::page(key, start_after, count)
+	}
+}
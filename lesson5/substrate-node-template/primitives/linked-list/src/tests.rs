@@ -0,0 +1,339 @@
+use super::*;
+use frame_support::{assert_ok, Blake2_128Concat, IterableStorageDoubleMap, storage::generator};
+use sp_io::TestExternalities;
+
+/// Items of the lists keyed by `(u64, Option<u32>)`.
+pub struct MapItems;
+
+impl generator::StorageMap<(u64, Option<u32>), LinkedItem<u32>> for MapItems {
+	type Query = Option<LinkedItem<u32>>;
+	type Hasher = Blake2_128Concat;
+
+	fn module_prefix() -> &'static [u8] {
+		b"LinkedListTest"
+	}
+
+	fn storage_prefix() -> &'static [u8] {
+		b"MapItems"
+	}
+
+	fn from_optional_value_to_query(v: Option<LinkedItem<u32>>) -> Self::Query {
+		v
+	}
+
+	fn from_query_to_optional_value(v: Self::Query) -> Option<LinkedItem<u32>> {
+		v
+	}
+}
+
+/// Items of the lists keyed by `u64, Option<u32>`.
+pub struct DoubleMapItems;
+
+impl generator::StorageDoubleMap<u64, Option<u32>, LinkedItem<u32>> for DoubleMapItems {
+	type Query = Option<LinkedItem<u32>>;
+	type Hasher1 = Blake2_128Concat;
+	type Hasher2 = Blake2_128Concat;
+
+	fn module_prefix() -> &'static [u8] {
+		b"LinkedListTest"
+	}
+
+	fn storage_prefix() -> &'static [u8] {
+		b"DoubleMapItems"
+	}
+
+	fn from_optional_value_to_query(v: Option<LinkedItem<u32>>) -> Self::Query {
+		v
+	}
+
+	fn from_query_to_optional_value(v: Self::Query) -> Option<LinkedItem<u32>> {
+		v
+	}
+}
+
+type MapList = LinkedList<MapLayout<MapItems>, u64, u32>;
+type DoubleMapList = LinkedList<DoubleMapLayout<DoubleMapItems>, u64, u32>;
+
+fn new_test_ext() -> TestExternalities {
+	TestExternalities::default()
+}
+
+#[test]
+fn linked_item_encodes_its_whole_length() {
+	let item = LinkedItem { prev: Some(1u32), next: None, len: 0 };
+	assert_eq!(item.encode(), (Some(1u32), None::<u32>, 0u32).encode());
+
+	// Decodes from a stream holding more than the item
+	let head = LinkedItem { prev: Some(2u32), next: Some(1), len: 2 };
+	let encoded = (&head, &item).encode();
+	assert_eq!(<(LinkedItem<u32>, LinkedItem<u32>)>::decode(&mut &encoded[..]), Ok((head, item)));
+}
+
+#[test]
+fn can_iterate_values() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), Vec::<u32>::new());
+
+		assert_ok!(MapList::append(&0, 1));
+		assert_ok!(MapList::append(&0, 2));
+		assert_ok!(MapList::append(&0, 3));
+		assert_ok!(MapList::append(&1, 4));
+
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+		assert_ok!(MapList::remove(&0, 2));
+
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![1, 3]);
+		assert_eq!(MapList::iter(&1).collect::<Vec<_>>(), vec![4]);
+	});
+}
+
+#[test]
+fn can_page_values() {
+	new_test_ext().execute_with(|| {
+		for i in 1..=5 {
+			assert_ok!(MapList::append(&0, i));
+		}
+
+		assert_eq!(MapList::page(&0, None, 2), vec![1, 2]);
+		assert_eq!(MapList::page(&0, Some(2), 2), vec![3, 4]);
+		assert_eq!(MapList::page(&0, Some(4), 2), vec![5]);
+		assert_eq!(MapList::page(&0, Some(5), 2), Vec::<u32>::new());
+		assert_eq!(MapList::page(&0, Some(9), 2), Vec::<u32>::new());
+		assert_eq!(MapList::page(&0, None, 0), Vec::<u32>::new());
+	});
+}
+
+#[test]
+fn remove_missing_value_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MapList::append(&0, 1));
+
+		assert_eq!(MapList::remove(&0, 2), Err(LinkedListError::ValueNotFound));
+		assert_eq!(MapList::remove(&1, 1), Err(LinkedListError::ValueNotFound));
+		assert_eq!(MapList::append(&0, 1), Err(LinkedListError::ValueAlreadyExists));
+		assert_eq!(MapList::len(&0), 1);
+	});
+}
+
+#[test]
+fn can_insert_and_pop_values() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MapList::append(&0, 2));
+		assert_ok!(MapList::insert_before(&0, 2, 1));
+		assert_ok!(MapList::insert_after(&0, 2, 4));
+		assert_ok!(MapList::insert_before(&0, 4, 3));
+		assert_eq!(MapList::insert_after(&0, 5, 6), Err(LinkedListError::ValueNotFound));
+		assert_eq!(MapList::insert_before(&0, 1, 3), Err(LinkedListError::ValueAlreadyExists));
+
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+		assert_eq!(MapList::len(&0), 4);
+		assert!(MapList::contains(&0, 3));
+		assert!(!MapList::contains(&1, 3));
+
+		assert_eq!(MapList::pop_front(&0), Some(1));
+		assert_eq!(MapList::pop_front(&0), Some(2));
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![3, 4]);
+		assert_eq!(MapList::len(&0), 2);
+		assert_eq!(MapList::pop_front(&1), None);
+	});
+}
+
+#[test]
+fn can_move_values() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(MapList::append(&0, 1));
+		assert_ok!(MapList::append(&0, 2));
+		assert_ok!(MapList::append(&1, 3));
+
+		assert_ok!(MapList::move_to(&0, &1, 1));
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![2]);
+		assert_eq!(MapList::iter(&1).collect::<Vec<_>>(), vec![3, 1]);
+
+		assert_eq!(MapList::move_to(&0, &1, 1), Err(LinkedListError::ValueNotFound));
+
+		assert_ok!(MapList::append(&0, 3));
+		assert_eq!(MapList::move_to(&0, &1, 3), Err(LinkedListError::ValueAlreadyExists));
+		assert_eq!(MapList::iter(&0).collect::<Vec<_>>(), vec![2, 3]);
+		assert_eq!(MapList::len(&0), 2);
+		assert_eq!(MapList::len(&1), 2);
+	});
+}
+
+#[test]
+fn double_map_items_are_stored_under_the_list_key() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DoubleMapList::append(&0, 1));
+		assert_ok!(DoubleMapList::append(&0, 2));
+		assert_ok!(DoubleMapList::append(&1, 3));
+
+		// The head and an item for each value
+		let mut items = DoubleMapItems::iter_prefix(0).map(|(value, _)| value).collect::<Vec<_>>();
+		items.sort();
+		assert_eq!(items, vec![None, Some(1), Some(2)]);
+
+		DoubleMapItems::remove_prefix(0);
+		assert_eq!(DoubleMapList::iter(&0).collect::<Vec<_>>(), Vec::<u32>::new());
+		assert_eq!(DoubleMapList::len(&0), 0);
+		assert_eq!(DoubleMapList::iter(&1).collect::<Vec<_>>(), vec![3]);
+	});
+}
+
+fn assert_list_matches<Layout: LinkedListStorage<u64, u32>>(key: u64, model: &[u32]) {
+	type List<Layout> = LinkedList<Layout, u64, u32>;
+
+	assert_eq!(List::<Layout>::iter(&key).collect::<Vec<_>>(), model.to_vec());
+	assert_eq!(List::<Layout>::len(&key), model.len() as u32);
+
+	let head = Layout::get(&key, &None).map(|head| (head.prev, head.next));
+	assert_eq!(head.unwrap_or((None, None)), (model.last().copied(), model.first().copied()));
+
+	for (i, value) in model.iter().enumerate() {
+		let item = Layout::get(&key, &Some(*value)).unwrap();
+		assert_eq!(item.prev, if i == 0 { None } else { Some(model[i - 1]) });
+		assert_eq!(item.next, model.get(i + 1).copied());
+		assert_eq!(item.len, 0);
+	}
+}
+
+fn random_operations_match_vec_model<Layout: LinkedListStorage<u64, u32>>() {
+	type List<Layout> = LinkedList<Layout, u64, u32>;
+
+	for seed in 1..=20u64 {
+		new_test_ext().execute_with(|| {
+			// xorshift, so the operation sequences are reproducible
+			let mut state = seed;
+			let mut random = |n: u64| {
+				state ^= state << 13;
+				state ^= state >> 7;
+				state ^= state << 17;
+				state % n
+			};
+			let mut model: Vec<Vec<u32>> = vec![vec![]; 3];
+
+			for _ in 0..200 {
+				let key = random(3);
+				let value = random(16) as u32;
+				let k = key as usize;
+				let position = |list: &Vec<u32>, value: u32| list.iter().position(|v| *v == value);
+
+				match random(7) {
+					0 => {
+						let expected = if position(&model[k], value).is_some() {
+							Err(LinkedListError::ValueAlreadyExists)
+						} else {
+							model[k].push(value);
+							Ok(())
+						};
+						assert_eq!(List::<Layout>::append(&key, value), expected);
+					},
+					1 => {
+						let expected = match position(&model[k], value) {
+							Some(i) => { model[k].remove(i); Ok(()) },
+							None => Err(LinkedListError::ValueNotFound),
+						};
+						assert_eq!(List::<Layout>::remove(&key, value), expected);
+					},
+					op @ 2 | op @ 3 => {
+						let target = random(16) as u32;
+						let expected = match position(&model[k], target) {
+							None => Err(LinkedListError::ValueNotFound),
+							Some(_) if position(&model[k], value).is_some() =>
+								Err(LinkedListError::ValueAlreadyExists),
+							Some(i) => {
+								model[k].insert(if op == 2 { i + 1 } else { i }, value);
+								Ok(())
+							},
+						};
+						let result = if op == 2 {
+							List::<Layout>::insert_after(&key, target, value)
+						} else {
+							List::<Layout>::insert_before(&key, target, value)
+						};
+						assert_eq!(result, expected);
+					},
+					4 => {
+						let expected = if model[k].is_empty() { None } else { Some(model[k].remove(0)) };
+						assert_eq!(List::<Layout>::pop_front(&key), expected);
+					},
+					5 => {
+						let key_to = random(3);
+						let k_to = key_to as usize;
+						let expected = match position(&model[k], value) {
+							None => Err(LinkedListError::ValueNotFound),
+							Some(_) if position(&model[k_to], value).is_some() =>
+								Err(LinkedListError::ValueAlreadyExists),
+							Some(i) => {
+								model[k].remove(i);
+								model[k_to].push(value);
+								Ok(())
+							},
+						};
+						assert_eq!(List::<Layout>::move_to(&key, &key_to, value), expected);
+					},
+					_ => {
+						assert_eq!(List::<Layout>::contains(&key, value), position(&model[k], value).is_some());
+					},
+				}
+
+				for key in 0..3 {
+					assert_list_matches::<Layout>(key, &model[key as usize]);
+				}
+			}
+		});
+	}
+}
+
+#[test]
+fn map_layout_random_operations_match_vec_model() {
+	random_operations_match_vec_model::<MapLayout<MapItems>>();
+}
+
+#[test]
+fn double_map_layout_random_operations_match_vec_model() {
+	random_operations_match_vec_model::<DoubleMapLayout<DoubleMapItems>>();
+}
+
+pub struct Tens;
+impl Convert<u32, u32> for Tens {
+	fn convert(value: u32) -> u32 {
+		value / 10
+	}
+}
+
+type SortedList = SortedLinkedList<MapLayout<MapItems>, u64, u32, u32, Tens>;
+
+#[test]
+fn sorted_list_keeps_values_sorted_by_weight() {
+	new_test_ext().execute_with(|| {
+		for value in &[35, 12, 40, 31, 5, 18] {
+			assert_ok!(SortedList::insert(&0, *value));
+		}
+
+		// Same weight keeps the insertion order
+		assert_eq!(SortedList::iter(&0).collect::<Vec<_>>(), vec![5, 12, 18, 35, 31, 40]);
+		assert_eq!(SortedList::len(&0), 6);
+		assert_eq!(SortedList::insert(&0, 12), Err(LinkedListError::ValueAlreadyExists));
+
+		assert_ok!(SortedList::remove(&0, 35));
+		assert_eq!(SortedList::pop_front(&0), Some(5));
+		assert_eq!(SortedList::page(&0, Some(18), 10), vec![31, 40]);
+	});
+}
+
+#[test]
+fn sorted_list_inserts_from_hint() {
+	new_test_ext().execute_with(|| {
+		for value in &[5, 12, 18, 31, 40] {
+			assert_ok!(SortedList::insert(&0, *value));
+		}
+
+		assert_eq!(SortedList::insert_with_hint(&0, 33, Some(40), 10), Err(LinkedListError::InvalidHint));
+		assert_eq!(SortedList::insert_with_hint(&0, 33, Some(9), 10), Err(LinkedListError::InvalidHint));
+		assert_eq!(SortedList::insert_with_hint(&0, 33, None, 2), Err(LinkedListError::HintTooFar));
+		assert_ok!(SortedList::insert_with_hint(&0, 33, Some(12), 2));
+		assert_eq!(SortedList::insert_with_hint(&0, 33, Some(12), 2), Err(LinkedListError::ValueAlreadyExists));
+
+		assert_eq!(SortedList::iter(&0).collect::<Vec<_>>(), vec![5, 12, 18, 31, 33, 40]);
+	});
+}
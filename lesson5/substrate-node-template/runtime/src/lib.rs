@@ -62,6 +62,9 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// Index of a kitty.
+pub type KittyIndex = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -96,7 +99,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 100,
+	spec_version: 102,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -298,12 +301,98 @@ impl pallet_template::Trait for Runtime {
 // 	type Event = Event;
 // }
 
-// impl pallet_kitties::Trait for Runtime {
-// 	type Event = Event;
-// 	type Randomness = RandomnessCollectiveFlip;
-// 	type KittyIndex = u32;
-// 	type Currency = Balances;
-// }
+parameter_types! {
+	pub const KittyDeposit: Balance = 1000 * MILLICENTS;
+	pub const KittyMutationRate: Permill = Permill::from_percent(2);
+	pub const MaxAskBookWalk: u32 = 20;
+	pub const MaxLeaseExpiries: u32 = 50;
+	pub const BreederRoyalty: Permill = Permill::from_percent(5);
+	pub const MaxKittiesPerAccount: u32 = 100;
+	pub const MaxMintsPerBlock: u32 = 50;
+	pub const MetadataDepositPerByte: Balance = 10 * MILLICENTS;
+	pub const MaxNameLength: u32 = 64;
+	pub const MaxUriLength: u32 = 256;
+	pub const KittyBirthDelay: BlockNumber = 2;
+	pub const MaxBirthsPerBlock: u32 = 50;
+	pub const MaxKittyBatchSize: u32 = 50;
+	pub const MaxKittyChildren: u32 = 20;
+}
+
+impl pallet_kitties::Trait for Runtime {
+	type Event = Event;
+	type Randomness = RandomnessCollectiveFlip;
+	type KittyIndex = KittyIndex;
+	type Currency = Balances;
+	type KittyDeposit = KittyDeposit;
+	type BreedingStrategy = pallet_kitties::MendelianBreeding<KittyMutationRate>;
+	type MaxAskBookWalk = MaxAskBookWalk;
+	type MaxLeaseExpiries = MaxLeaseExpiries;
+	type BreederRoyalty = BreederRoyalty;
+	type MaxKittiesPerAccount = MaxKittiesPerAccount;
+	type MaxMintsPerBlock = MaxMintsPerBlock;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type MaxNameLength = MaxNameLength;
+	type MaxUriLength = MaxUriLength;
+	type BirthDelay = KittyBirthDelay;
+	type Genes = pallet_kitties::KittyGenes;
+	type MaxBirthsPerBlock = MaxBirthsPerBlock;
+	type MaxBatchSize = MaxKittyBatchSize;
+	type MaxChildren = MaxKittyChildren;
+	type OnKittyBurned = Battle;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const DogDeposit: Balance = 500 * MILLICENTS;
+	pub const DogGenes: pallet_kitties::Genes = [(4, 3), (3, 5), (2, 2), (1, 4), (0, 6)];
+}
+
+/// A second collection with its own storage, deposit and gene layout, battles use the kitties only.
+impl pallet_kitties::Trait<pallet_kitties::Instance1> for Runtime {
+	type Event = Event;
+	type Randomness = RandomnessCollectiveFlip;
+	type KittyIndex = KittyIndex;
+	type Currency = Balances;
+	type KittyDeposit = DogDeposit;
+	type BreedingStrategy = pallet_kitties::MendelianBreeding<KittyMutationRate>;
+	type MaxAskBookWalk = MaxAskBookWalk;
+	type MaxLeaseExpiries = MaxLeaseExpiries;
+	type BreederRoyalty = BreederRoyalty;
+	type MaxKittiesPerAccount = MaxKittiesPerAccount;
+	type MaxMintsPerBlock = MaxMintsPerBlock;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type MaxNameLength = MaxNameLength;
+	type MaxUriLength = MaxUriLength;
+	type BirthDelay = KittyBirthDelay;
+	type Genes = DogGenes;
+	type MaxBirthsPerBlock = MaxBirthsPerBlock;
+	type MaxBatchSize = MaxKittyBatchSize;
+	type MaxChildren = MaxKittyChildren;
+	type OnKittyBurned = ();
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const BattleDelay: BlockNumber = 2;
+	pub const MaxBattlesPerBlock: u32 = 50;
+	pub const RematchCooldown: BlockNumber = HOURS;
+}
+
+impl pallet_battle::Trait for Runtime {
+	type Event = Event;
+	type BattleDelay = BattleDelay;
+	type MaxBattlesPerBlock = MaxBattlesPerBlock;
+	type RematchCooldown = RematchCooldown;
+}
+
+parameter_types! {
+	pub const FractionalModuleId: sp_runtime::ModuleId = sp_runtime::ModuleId(*b"py/fract");
+}
+
+impl pallet_fractional::Trait for Runtime {
+	type Event = Event;
+	type ModuleId = FractionalModuleId;
+}
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
 construct_runtime!(
@@ -324,7 +413,10 @@ construct_runtime!(
 		// Include the custom logic from the template pallet in the runtime.
 		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
 		// PoeModule: pallet_poe::{Module, Call, Storage, Event<T>},
-		// KittiesModule: pallet_kitties::{Module, Call, Storage, Event<T>},
+		KittiesModule: pallet_kitties::{Module, Call, Storage, Event<T>, Config<T>},
+		Battle: pallet_battle::{Module, Call, Storage, Event<T>},
+		Fractional: pallet_fractional::{Module, Call, Storage, Event<T>},
+		DogsModule: pallet_kitties::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -515,6 +607,34 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_runtime_api::KittiesApi<Block, AccountId, KittyIndex, Balance> for Runtime {
+		fn decode_dna(kitty_id: KittyIndex) -> Option<pallet_kitties::DecodedDna> {
+			KittiesModule::decode_dna(kitty_id)
+		}
+
+		fn owned_kitties(
+			account: AccountId,
+			start_after: Option<KittyIndex>,
+			count: u32,
+		) -> Vec<KittyIndex> {
+			KittiesModule::owned_kitties_page(&account, start_after, count)
+		}
+
+		fn cheapest_asks(count: u32) -> Vec<(KittyIndex, Balance)> {
+			KittiesModule::cheapest_asks(count)
+		}
+
+		fn kitty_info(kitty_id: KittyIndex) -> Option<pallet_kitties::KittyInfo<AccountId, Balance>> {
+			KittiesModule::kitty_info(kitty_id)
+		}
+	}
+
+	impl pallet_battle_runtime_api::BattleApi<Block, KittyIndex> for Runtime {
+		fn leaderboard(count: u32) -> Vec<(KittyIndex, pallet_battle::BattleRecord)> {
+			Battle::leaderboard(count)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance>
 		for Runtime {
 		fn query_info(
@@ -560,9 +680,50 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
 			add_benchmark!(params, batches, pallet_contracts, Contracts);
+			add_benchmark!(params, batches, pallet_kitties, KittiesModule);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::assert_ok;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		pallet_balances::GenesisConfig::<Runtime> {
+			balances: vec![([1u8; 32].into(), 1_000_000 * MILLICENTS), ([2u8; 32].into(), 1_000_000 * MILLICENTS)],
+		}.assimilate_storage(&mut t).unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		// The randomness of block 0 is not defined
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn kitties_and_dogs_have_isolated_storage() {
+		new_test_ext().execute_with(|| {
+			let alice: AccountId = [1u8; 32].into();
+			let bob: AccountId = [2u8; 32].into();
+			assert_ok!(KittiesModule::create(Origin::signed(alice.clone())));
+			assert_ok!(KittiesModule::create(Origin::signed(alice.clone())));
+			assert_ok!(DogsModule::create(Origin::signed(bob.clone())));
+
+			// Both collections allocate ids from 0 and reserve their own deposit
+			assert_eq!(KittiesModule::kitties_count(), 2);
+			assert_eq!(DogsModule::kitties_count(), 1);
+			assert_eq!(Balances::reserved_balance(&alice), 2 * KittyDeposit::get());
+			assert_eq!(Balances::reserved_balance(&bob), DogDeposit::get());
+
+			// The births are pending until the randomness of a later block
+			assert_eq!(KittiesModule::pending_birth(0).map(|birth| birth.owner), Some(alice.clone()));
+			assert_eq!(DogsModule::pending_birth(0).map(|birth| birth.owner), Some(bob.clone()));
+			assert_eq!(KittiesModule::pending_birth_count(&bob), 0);
+			assert_eq!(DogsModule::pending_birth_count(&alice), 0);
+		});
+	}
+}